@@ -0,0 +1,92 @@
+use crate::bounding_box::BoundingBox;
+use crate::ray::Ray;
+use crate::shape::Shape;
+use crate::vector::Vector3D;
+
+/// A solid box whose faces are aligned with the coordinate axes, spanning
+/// from the `min` to the `max` corner.
+#[derive(Debug, Clone)]
+pub struct AxisAlignedBox {
+  pub min: Vector3D,
+  pub max: Vector3D,
+}
+
+impl AxisAlignedBox {
+  /// Distances along `ray` at which its line enters and exits the box.
+  pub fn intersections(&self, ray: &Ray) -> Option<(f32, f32)> {
+    let mut t_near = f32::NEG_INFINITY;
+    let mut t_far = f32::INFINITY;
+
+    let axes = [
+      (ray.origin.x, ray.direction.x, self.min.x, self.max.x),
+      (ray.origin.y, ray.direction.y, self.min.y, self.max.y),
+      (ray.origin.z, ray.direction.z, self.min.z, self.max.z),
+    ];
+
+    for &(origin, direction, min, max) in &axes {
+      // a zero direction gives infinite slab distances with matching signs,
+      // which correctly accepts or rejects the ray based on its origin
+      let inverse = 1.0 / direction;
+      let mut t0 = (min - origin) * inverse;
+      let mut t1 = (max - origin) * inverse;
+      if t0 > t1 { std::mem::swap(&mut t0, &mut t1); }
+
+      // NaN only arises for origins exactly on a slab boundary of a parallel
+      // ray; the comparisons below then leave the interval untouched
+      if t0 > t_near { t_near = t0; }
+      if t1 < t_far { t_far = t1; }
+      if t_near > t_far { return None; }
+    }
+
+    Some((t_near, t_far))
+  }
+
+  pub fn intersects(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<f32> {
+    let (t0, t1) = self.intersections(ray)?;
+
+    if t0 > t_min && t0 < t_max {
+      Some(t0)
+    } else if t1 > t_min && t1 < t_max {
+      Some(t1)
+    } else {
+      None
+    }
+  }
+
+  /// The normal of the face nearest to `hit_point`.
+  pub fn surface_normal(&self, hit_point: &Vector3D) -> Vector3D {
+    let faces = [
+      (hit_point.x - self.min.x, Vector3D::new(-1.0, 0.0, 0.0)),
+      (self.max.x - hit_point.x, Vector3D::new(1.0, 0.0, 0.0)),
+      (hit_point.y - self.min.y, Vector3D::new(0.0, -1.0, 0.0)),
+      (self.max.y - hit_point.y, Vector3D::new(0.0, 1.0, 0.0)),
+      (hit_point.z - self.min.z, Vector3D::new(0.0, 0.0, -1.0)),
+      (self.max.z - hit_point.z, Vector3D::new(0.0, 0.0, 1.0)),
+    ];
+
+    let mut normal = faces[0].1;
+    let mut nearest = f32::INFINITY;
+    for &(distance, face_normal) in &faces {
+      if distance.abs() < nearest {
+        nearest = distance.abs();
+        normal = face_normal;
+      }
+    }
+
+    normal
+  }
+}
+
+impl Shape for AxisAlignedBox {
+  fn intersect(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<f32> {
+    self.intersects(ray, t_min, t_max)
+  }
+
+  fn normal(&self, point: &Vector3D) -> Vector3D {
+    self.surface_normal(point)
+  }
+
+  fn bounding_box(&self) -> BoundingBox {
+    BoundingBox::new(self.min, self.max)
+  }
+}
@@ -0,0 +1,73 @@
+use crate::ray::Ray;
+use crate::vector::Vector3D;
+
+/// An axis-aligned region of space enclosing a shape.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+  pub min: Vector3D,
+  pub max: Vector3D,
+}
+
+impl BoundingBox {
+  pub fn new(min: Vector3D, max: Vector3D) -> BoundingBox {
+    BoundingBox { min, max }
+  }
+
+  /// A box containing nothing, the identity for `union`.
+  pub fn empty() -> BoundingBox {
+    BoundingBox {
+      min: Vector3D::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+      max: Vector3D::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+    }
+  }
+
+  /// A box containing all of space, for shapes such as planes.
+  pub fn infinite() -> BoundingBox {
+    BoundingBox {
+      min: Vector3D::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+      max: Vector3D::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+    }
+  }
+
+  pub fn is_finite(&self) -> bool {
+    self.min.x.is_finite() && self.min.y.is_finite() && self.min.z.is_finite()
+      && self.max.x.is_finite() && self.max.y.is_finite() && self.max.z.is_finite()
+  }
+
+  pub fn union(&self, other: &BoundingBox) -> BoundingBox {
+    BoundingBox { min: self.min.min(&other.min), max: self.max.max(&other.max) }
+  }
+
+  pub fn grow(&self, point: &Vector3D) -> BoundingBox {
+    BoundingBox { min: self.min.min(point), max: self.max.max(point) }
+  }
+
+  pub fn centroid(&self) -> Vector3D {
+    (self.min + self.max) * 0.5
+  }
+
+  pub fn surface_area(&self) -> f32 {
+    let d = self.max - self.min;
+    if d.x < 0.0 || d.y < 0.0 || d.z < 0.0 { return 0.0; }
+    2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+  }
+
+  /// Slab test against `ray`, given the reciprocal of its direction. Returns
+  /// the distance at which the ray enters the box within `(t_min, t_max)`.
+  pub fn intersects(&self, ray: &Ray, inverse_direction: &Vector3D, t_min: f32, t_max: f32) -> Option<f32> {
+    let mut t_near = t_min;
+    let mut t_far = t_max;
+
+    for axis in 0..3 {
+      let mut t0 = (self.min[axis] - ray.origin[axis]) * inverse_direction[axis];
+      let mut t1 = (self.max[axis] - ray.origin[axis]) * inverse_direction[axis];
+      if t0 > t1 { std::mem::swap(&mut t0, &mut t1); }
+
+      if t0 > t_near { t_near = t0; }
+      if t1 < t_far { t_far = t1; }
+      if t_near > t_far { return None; }
+    }
+
+    Some(t_near)
+  }
+}
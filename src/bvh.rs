@@ -0,0 +1,351 @@
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::bounding_box::BoundingBox;
+use crate::ray::Ray;
+use crate::vector::Vector3D;
+
+/// Number of buckets centroids are sorted into when evaluating splits.
+const SAH_BINS: usize = 16;
+/// Cost of visiting a node relative to testing a primitive.
+const TRAVERSAL_COST: f32 = 1.0;
+/// Leaves never grow beyond this many primitives unless they can't be split.
+const MAX_LEAF_SIZE: usize = 8;
+
+/// Counters describing how much work queries against a `Bvh` have done.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BvhStats {
+  /// Number of closest-hit and any-hit queries.
+  pub rays: u64,
+  pub node_visits: u64,
+  pub primitive_tests: u64,
+}
+
+thread_local! {
+  /// Work done by queries on this thread that hasn't been added to a `Bvh`'s
+  /// totals yet, so render threads don't contend on shared counters for
+  /// every ray.
+  static PENDING: Cell<BvhStats> = const { Cell::new(BvhStats { rays: 0, node_visits: 0, primitive_tests: 0 }) };
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+  rays: AtomicU64,
+  node_visits: AtomicU64,
+  primitive_tests: AtomicU64,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+  bounds: BoundingBox,
+  /// First primitive for leaves, index of the second child for interior
+  /// nodes. The first child always directly follows its parent.
+  offset: usize,
+  /// Number of primitives in a leaf, zero for interior nodes.
+  count: usize,
+  /// Axis interior nodes were split along, used to visit the nearer child
+  /// first.
+  axis: usize,
+}
+
+/// A bounding volume hierarchy over a list of primitives, built with the
+/// surface area heuristic. Primitives with unbounded extents are kept out of
+/// the tree and tested against every ray.
+#[derive(Debug, Default)]
+pub struct Bvh {
+  nodes: Vec<Node>,
+  /// Primitive indices in the order leaves reference them.
+  indices: Vec<usize>,
+  unbounded: Vec<usize>,
+  counters: Counters,
+}
+
+struct BuildItem {
+  index: usize,
+  bounds: BoundingBox,
+  centroid: Vector3D,
+}
+
+impl Bvh {
+  /// Builds a hierarchy over primitives with the given bounding boxes.
+  pub fn build(bounds: &[BoundingBox]) -> Bvh {
+    let mut items = Vec::with_capacity(bounds.len());
+    let mut unbounded = Vec::new();
+
+    for (index, b) in bounds.iter().enumerate() {
+      if b.is_finite() {
+        items.push(BuildItem { index, bounds: *b, centroid: b.centroid() });
+      } else {
+        unbounded.push(index);
+      }
+    }
+
+    let mut bvh = Bvh { nodes: Vec::new(), indices: Vec::with_capacity(items.len()), unbounded, counters: Counters::default() };
+    if !items.is_empty() {
+      bvh.build_node(&mut items);
+    }
+    bvh
+  }
+
+  /// Appends the subtree for `items` to `nodes`, returning its index.
+  fn build_node(&mut self, items: &mut [BuildItem]) -> usize {
+    let bounds = items.iter().fold(BoundingBox::empty(), |b, item| b.union(&item.bounds));
+    let centroid_bounds = items.iter().fold(BoundingBox::empty(), |b, item| b.grow(&item.centroid));
+
+    let node = self.nodes.len();
+    self.nodes.push(Node { bounds, offset: self.indices.len(), count: items.len(), axis: 0 });
+
+    if items.len() <= 1 {
+      self.make_leaf(items);
+      return node;
+    }
+
+    let split = match find_split(items, &bounds, &centroid_bounds) {
+      Some(split) => split,
+      None => {
+        self.make_leaf(items);
+        return node;
+      }
+    };
+
+    let (axis, mid) = split;
+    self.build_node(&mut items[..mid]);
+    let right = self.build_node(&mut items[mid..]);
+
+    self.nodes[node].offset = right;
+    self.nodes[node].count = 0;
+    self.nodes[node].axis = axis;
+    node
+  }
+
+  fn make_leaf(&mut self, items: &[BuildItem]) {
+    self.indices.extend(items.iter().map(|item| item.index));
+  }
+
+  /// Finds the nearest primitive along `ray` within `(t_min, t_max)`.
+  /// `intersect` tests a single primitive against the ray up to a maximum
+  /// distance, returning where it was hit.
+  pub fn intersect<F>(&self, ray: &Ray, t_min: f32, t_max: f32, intersect: F) -> Option<(usize, f32)>
+  where F: Fn(usize, f32) -> Option<f32> {
+    let mut nearest = None;
+    let mut closest = t_max;
+    let mut node_visits = 0;
+    let mut primitive_tests = self.unbounded.len() as u64;
+
+    for &index in &self.unbounded {
+      if let Some(t) = intersect(index, closest) {
+        closest = t;
+        nearest = Some(index);
+      }
+    }
+
+    if !self.nodes.is_empty() {
+      let inverse_direction = inverse(&ray.direction);
+      let mut stack = Vec::with_capacity(64);
+      stack.push(0);
+
+      while let Some(current) = stack.pop() {
+        node_visits += 1;
+        let node = &self.nodes[current];
+        if node.bounds.intersects(ray, &inverse_direction, t_min, closest).is_none() { continue; }
+
+        if node.count > 0 {
+          primitive_tests += node.count as u64;
+          for &index in &self.indices[node.offset..node.offset + node.count] {
+            if let Some(t) = intersect(index, closest) {
+              closest = t;
+              nearest = Some(index);
+            }
+          }
+        } else if ray.direction[node.axis] < 0.0 {
+          stack.push(current + 1);
+          stack.push(node.offset);
+        } else {
+          stack.push(node.offset);
+          stack.push(current + 1);
+        }
+      }
+    }
+
+    self.record(node_visits, primitive_tests);
+    nearest.map(|index| (index, closest))
+  }
+
+  /// Whether any primitive intersects `ray` within `(t_min, t_max)`. Stops at
+  /// the first hit found rather than looking for the nearest.
+  pub fn any_hit<F>(&self, ray: &Ray, t_min: f32, t_max: f32, intersect: F) -> bool
+  where F: Fn(usize, f32) -> Option<f32> {
+    let mut node_visits = 0;
+    let mut primitive_tests = 0;
+    let hit = self.find_any(ray, t_min, t_max, &intersect, &mut node_visits, &mut primitive_tests);
+    self.record(node_visits, primitive_tests);
+    hit
+  }
+
+  fn find_any<F>(&self, ray: &Ray, t_min: f32, t_max: f32, intersect: &F, node_visits: &mut u64, primitive_tests: &mut u64) -> bool
+  where F: Fn(usize, f32) -> Option<f32> {
+    for &index in &self.unbounded {
+      *primitive_tests += 1;
+      if intersect(index, t_max).is_some() { return true; }
+    }
+
+    if self.nodes.is_empty() { return false; }
+
+    let inverse_direction = inverse(&ray.direction);
+    let mut stack = Vec::with_capacity(64);
+    stack.push(0);
+
+    while let Some(current) = stack.pop() {
+      *node_visits += 1;
+      let node = &self.nodes[current];
+      if node.bounds.intersects(ray, &inverse_direction, t_min, t_max).is_none() { continue; }
+
+      if node.count > 0 {
+        for &index in &self.indices[node.offset..node.offset + node.count] {
+          *primitive_tests += 1;
+          if intersect(index, t_max).is_some() { return true; }
+        }
+      } else {
+        stack.push(node.offset);
+        stack.push(current + 1);
+      }
+    }
+
+    false
+  }
+
+  fn record(&self, node_visits: u64, primitive_tests: u64) {
+    PENDING.with(|pending| {
+      let stats = pending.get();
+      pending.set(BvhStats {
+        rays: stats.rays + 1,
+        node_visits: stats.node_visits + node_visits,
+        primitive_tests: stats.primitive_tests + primitive_tests,
+      });
+    });
+  }
+
+  /// Adds the work done by queries on the calling thread since it last
+  /// flushed to this hierarchy's totals. Renderers call this once per tile.
+  pub fn flush_stats(&self) {
+    let pending = PENDING.with(|pending| pending.take());
+    if pending.rays == 0 { return; }
+    self.counters.rays.fetch_add(pending.rays, Ordering::Relaxed);
+    self.counters.node_visits.fetch_add(pending.node_visits, Ordering::Relaxed);
+    self.counters.primitive_tests.fetch_add(pending.primitive_tests, Ordering::Relaxed);
+  }
+
+  /// Work done by all queries since the hierarchy was built or last reset,
+  /// counting those on other threads once they have flushed.
+  pub fn stats(&self) -> BvhStats {
+    self.flush_stats();
+    BvhStats {
+      rays: self.counters.rays.load(Ordering::Relaxed),
+      node_visits: self.counters.node_visits.load(Ordering::Relaxed),
+      primitive_tests: self.counters.primitive_tests.load(Ordering::Relaxed),
+    }
+  }
+
+  pub fn reset_stats(&self) {
+    PENDING.with(|pending| pending.take());
+    self.counters.rays.store(0, Ordering::Relaxed);
+    self.counters.node_visits.store(0, Ordering::Relaxed);
+    self.counters.primitive_tests.store(0, Ordering::Relaxed);
+  }
+
+  pub fn node_count(&self) -> usize {
+    self.nodes.len()
+  }
+
+  /// Bounds of everything in the tree, excluding unbounded primitives.
+  pub fn bounds(&self) -> BoundingBox {
+    self.nodes.first().map_or(BoundingBox::empty(), |node| node.bounds)
+  }
+}
+
+fn inverse(direction: &Vector3D) -> Vector3D {
+  Vector3D::new(1.0 / direction.x, 1.0 / direction.y, 1.0 / direction.z)
+}
+
+/// Picks the cheapest split of `items` according to the surface area
+/// heuristic, partitioning them in place. Returns the split axis and the
+/// index of the first item on the right, or `None` when a leaf is cheaper.
+fn find_split(items: &mut [BuildItem], bounds: &BoundingBox, centroid_bounds: &BoundingBox) -> Option<(usize, usize)> {
+  let extent = centroid_bounds.max - centroid_bounds.min;
+  let axis = if extent.x > extent.y && extent.x > extent.z { 0 } else if extent.y > extent.z { 1 } else { 2 };
+
+  let min = centroid_bounds.min[axis];
+  let width = extent[axis];
+  if width <= 0.0 {
+    // every centroid coincides, so no plane can separate them; fall back to
+    // an arbitrary even split if the leaf would otherwise be too large
+    if items.len() <= MAX_LEAF_SIZE { return None; }
+    return Some((axis, median_split(items, axis)));
+  }
+
+  let bin_of = |item: &BuildItem| {
+    let bin = ((item.centroid[axis] - min) / width * SAH_BINS as f32) as usize;
+    bin.min(SAH_BINS - 1)
+  };
+
+  let mut bin_bounds = [BoundingBox::empty(); SAH_BINS];
+  let mut bin_counts = [0usize; SAH_BINS];
+  for item in items.iter() {
+    let bin = bin_of(item);
+    bin_counts[bin] += 1;
+    bin_bounds[bin] = bin_bounds[bin].union(&item.bounds);
+  }
+
+  // sweep from the right to get the area and count of every suffix of bins
+  let mut right_area = [0.0; SAH_BINS];
+  let mut right_count = [0; SAH_BINS];
+  let mut running = BoundingBox::empty();
+  let mut count = 0;
+  for bin in (1..SAH_BINS).rev() {
+    running = running.union(&bin_bounds[bin]);
+    count += bin_counts[bin];
+    right_area[bin] = running.surface_area();
+    right_count[bin] = count;
+  }
+
+  let mut best_cost = f32::INFINITY;
+  let mut best_bin = 0;
+  let mut running = BoundingBox::empty();
+  let mut count = 0;
+  for bin in 1..SAH_BINS {
+    running = running.union(&bin_bounds[bin - 1]);
+    count += bin_counts[bin - 1];
+    if count == 0 || right_count[bin] == 0 { continue; }
+
+    let cost = running.surface_area() * count as f32 + right_area[bin] * right_count[bin] as f32;
+    if cost < best_cost {
+      best_cost = cost;
+      best_bin = bin;
+    }
+  }
+
+  let area = bounds.surface_area();
+  let split_cost = TRAVERSAL_COST + if area > 0.0 { best_cost / area } else { 0.0 };
+  let leaf_cost = items.len() as f32;
+  if best_bin == 0 || (split_cost >= leaf_cost && items.len() <= MAX_LEAF_SIZE) {
+    if items.len() <= MAX_LEAF_SIZE { return None; }
+    return Some((axis, median_split(items, axis)));
+  }
+
+  let mut mid = 0;
+  for i in 0..items.len() {
+    if bin_of(&items[i]) < best_bin {
+      items.swap(i, mid);
+      mid += 1;
+    }
+  }
+
+  Some((axis, mid))
+}
+
+/// Splits `items` into two equal halves by their centroids along `axis`.
+fn median_split(items: &mut [BuildItem], axis: usize) -> usize {
+  let mid = items.len() / 2;
+  items.select_nth_unstable_by(mid, |a, b| a.centroid[axis].total_cmp(&b.centroid[axis]));
+  mid
+}
@@ -0,0 +1,160 @@
+use std::f32::consts::PI;
+
+use crate::ray::Ray;
+use crate::sampling::concentric_disc;
+use crate::vector::Vector3D;
+
+/// How directions around the camera map onto the image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+  /// A regular lens, where straight lines stay straight.
+  Perspective,
+  /// Parallel rays, `height` world units tall, so distance doesn't change
+  /// the size of things.
+  Orthographic { height: f32 },
+  /// An equidistant fisheye, where the distance from the center of the image
+  /// is proportional to the angle from the view direction. `fov` is the
+  /// angle across the circle fitting the image height, and pixels outside it
+  /// see nothing.
+  Fisheye,
+  /// A full 360 by 180 degree latitude-longitude panorama, best rendered at
+  /// a 2:1 aspect ratio.
+  Equirectangular,
+}
+
+/// A camera at `position` looking towards `target`. With a zero aperture
+/// it's a pinhole and everything is in focus, otherwise it models a thin
+/// lens for depth of field.
+#[derive(Debug, Clone)]
+pub struct Camera {
+  pub position: Vector3D,
+  pub target: Vector3D,
+  /// The direction that appears upwards in the image. It only needs to
+  /// point roughly up, it's straightened out against the view direction.
+  pub up: Vector3D,
+  pub projection: Projection,
+  /// Vertical field of view in degrees. The horizontal one follows from the
+  /// aspect ratio of the image.
+  pub fov: f32,
+  /// Radius of the lens. Larger apertures blur everything away from the
+  /// focus distance more. Panoramic projections ignore it.
+  pub aperture: f32,
+  /// Distance along the view direction to the plane that's in focus.
+  pub focus_distance: f32,
+  /// Number of straight aperture blades shaping out of focus highlights,
+  /// or zero for a round aperture.
+  pub blades: u32,
+  /// Rotation of the blades in degrees.
+  pub blade_rotation: f32,
+}
+
+impl Default for Camera {
+  /// Looks down the negative z axis from the origin with a 37 degree field
+  /// of view, the image plane spanning `[-1, 1]` vertically at distance 3.
+  fn default() -> Camera {
+    let fov = (1.0f32 / 3.0).atan().to_degrees() * 2.0;
+    Camera::look_at(Vector3D::new(0.0, 0.0, 0.0), Vector3D::new(0.0, 0.0, -1.0), fov)
+  }
+}
+
+impl Camera {
+  /// A pinhole camera at `position` aimed at `target`, with the y axis up.
+  /// Giving it an aperture keeps `target` in focus.
+  pub fn look_at(position: Vector3D, target: Vector3D, fov: f32) -> Camera {
+    Camera {
+      position,
+      target,
+      up: Vector3D::new(0.0, 1.0, 0.0),
+      projection: Projection::Perspective,
+      fov,
+      aperture: 0.0,
+      focus_distance: (target - position).magnitude(),
+      blades: 0,
+      blade_rotation: 0.0,
+    }
+  }
+
+  /// The unit vectors pointing right, up and forward from the camera.
+  pub fn basis(&self) -> (Vector3D, Vector3D, Vector3D) {
+    let forward = (self.target - self.position).normalize();
+    let mut right = forward.cross(&self.up);
+    if right.magnitude() < 1e-6 {
+      // looking straight along `up`, any perpendicular will do
+      let fallback = if forward.x.abs() < 0.9 { Vector3D::new(1.0, 0.0, 0.0) } else { Vector3D::new(0.0, 0.0, 1.0) };
+      right = forward.cross(&fallback);
+    }
+    let right = right.normalize();
+    (right, right.cross(&forward), forward)
+  }
+
+  /// Builds the primary ray through the normalized image coordinates
+  /// `(u, v)`, where `(0, 0)` is the top left corner of the image and
+  /// `aspect` is its width over its height. The ray starts at the center of
+  /// the lens, and there is none for points outside a fisheye's circle.
+  pub fn ray(&self, u: f32, v: f32, aspect: f32) -> Option<Ray> {
+    self.lens_ray(u, v, aspect, (0.5, 0.5))
+  }
+
+  /// Like `ray`, but starting from the point of the lens picked by `lens`,
+  /// two numbers in `[0, 1)`.
+  pub fn lens_ray(&self, u: f32, v: f32, aspect: f32, lens: (f32, f32)) -> Option<Ray> {
+    let (right, up, forward) = self.basis();
+    let x = (u * 2.0 - 1.0) * aspect;
+    let y = 1.0 - v * 2.0;
+
+    // where the ray would start without a lens, and a direction it would
+    // travel one unit along the view direction for
+    let (origin, direction) = match self.projection {
+      Projection::Perspective => {
+        let half_height = (self.fov.to_radians() * 0.5).tan();
+        (self.position, forward + (right * x + up * y) * half_height)
+      },
+      Projection::Orthographic { height } => (self.position + (right * x + up * y) * (height * 0.5), forward),
+      Projection::Fisheye => {
+        let r = x.hypot(y);
+        if r > 1.0 { return None; }
+        let theta = r * self.fov.to_radians() * 0.5;
+        let (sin_phi, cos_phi) = if r > 0.0 { (y / r, x / r) } else { (0.0, 1.0) };
+        let direction = forward * theta.cos() + (right * cos_phi + up * sin_phi) * theta.sin();
+        return Some(Ray::new(self.position, direction));
+      },
+      Projection::Equirectangular => {
+        let longitude = (u - 0.5) * 2.0 * PI;
+        let latitude = (0.5 - v) * PI;
+        let horizontal = forward * longitude.cos() + right * longitude.sin();
+        return Some(Ray::new(self.position, horizontal * latitude.cos() + up * latitude.sin()));
+      },
+    };
+
+    if self.aperture <= 0.0 {
+      return Some(Ray::new(origin, direction.normalize()));
+    }
+
+    // every ray through this pixel meets at the same point on the focus
+    // plane, whichever part of the lens it leaves from
+    let focus = origin + direction * self.focus_distance;
+    let (lx, ly) = self.aperture_point(lens);
+    let origin = origin + (right * lx + up * ly) * self.aperture;
+    Some(Ray::new(origin, (focus - origin).normalize()))
+  }
+
+  /// Maps `(s, t)` in the unit square evenly onto the aperture shape, a
+  /// unit disc or a regular polygon inscribed in it.
+  pub fn aperture_point(&self, (s, t): (f32, f32)) -> (f32, f32) {
+    if self.blades < 3 {
+      return concentric_disc(s, t);
+    }
+
+    // pick one of the triangles fanning out from the center, then a point
+    // in it
+    let blades = self.blades as f32;
+    let blade = (s * blades).floor().min(blades - 1.0);
+    let s = s * blades - blade;
+    let step = std::f32::consts::TAU / blades;
+    let start = self.blade_rotation.to_radians() + blade * step;
+    let (a, b) = ((start.cos(), start.sin()), ((start + step).cos(), (start + step).sin()));
+
+    let r = s.sqrt();
+    (r * (a.0 + (b.0 - a.0) * t), r * (a.1 + (b.1 - a.1) * t))
+  }
+}
@@ -0,0 +1,192 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use trace::format::exr::{Compression, PixelType};
+use trace::renderer::MAX_DIMENSION;
+use trace::{Dither, Filter, SaveOptions, ToneMapper};
+
+pub const USAGE: &str = "\
+usage: trace render <scene> [options]
+       trace --help
+
+Renders a scene description file into an image.
+
+options:
+  -o, --output <path>    image to write, its extension picks the format
+                         (default: out.ppm)
+      --width <pixels>   image width, overriding the scene's settings
+      --height <pixels>  image height, overriding the scene's settings
+      --spp <count>      samples per pixel
+      --filter <name>    box, tent, gaussian, mitchell or lanczos
+                         reconstruction filter
+      --threads <count>  worker threads, 0 uses every core
+      --tile-size <px>   edge length of the tiles handed to each thread
+      --exposure <stops> brighten or darken .png and .ppm output
+      --tonemap <curve>  clamp, reinhard, reinhard-extended, aces or hable
+                         (default: clamp)
+      --white <value>    radiance mapped to white by reinhard-extended
+                         (default: 4)
+      --dither <kind>    none, ordered or blue-noise (default: none)
+      --exr-compression <none|rle|zip>
+                         compression of .exr output (default: zip)
+      --exr-float        store the .exr beauty pass as 32-bit floats
+                         rather than half floats
+  -q, --quiet            don't print render statistics
+  -h, --help             print this message";
+
+/// Most samples per pixel accepted, well past anything that finishes in
+/// reasonable time.
+const MAX_SAMPLES: usize = 1 << 16;
+/// Radiance mapped to white by `--tonemap reinhard-extended` unless given.
+const DEFAULT_WHITE: f32 = 4.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+  Render(RenderArgs),
+  Help,
+}
+
+/// Options for `trace render`. Unset values fall back to the scene file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderArgs {
+  pub scene: PathBuf,
+  pub output: PathBuf,
+  pub width: Option<usize>,
+  pub height: Option<usize>,
+  pub samples: Option<usize>,
+  pub filter: Option<Filter>,
+  pub threads: Option<usize>,
+  pub tile_size: Option<usize>,
+  /// How the image is encoded, whatever the output format.
+  pub save: SaveOptions,
+  pub quiet: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UsageError(pub String);
+
+impl fmt::Display for UsageError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str(&self.0)
+  }
+}
+
+/// Parses the command line, not including the program name.
+pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Command, UsageError> {
+  let mut args = args.into_iter();
+
+  match args.next().as_deref() {
+    Some("render") => parse_render(args),
+    Some("-h") | Some("--help") | Some("help") => Ok(Command::Help),
+    Some(other) => Err(UsageError(format!("unknown command `{}`", other))),
+    None => Err(UsageError("missing command".to_string())),
+  }
+}
+
+fn parse_render<I: Iterator<Item = String>>(mut args: I) -> Result<Command, UsageError> {
+  let mut scene = None;
+  let mut render = RenderArgs {
+    scene: PathBuf::new(),
+    output: PathBuf::from("out.ppm"),
+    width: None,
+    height: None,
+    samples: None,
+    filter: None,
+    threads: None,
+    tile_size: None,
+    save: SaveOptions::default(),
+    quiet: false,
+  };
+  let mut white = None;
+
+  while let Some(arg) = args.next() {
+    // accept both `--width 10` and `--width=10`
+    let (flag, inline) = match arg.find('=') {
+      Some(i) if arg.starts_with("--") => (arg[..i].to_string(), Some(arg[i + 1..].to_string())),
+      _ => (arg.clone(), None),
+    };
+
+    let mut value = |name: &str| -> Result<String, UsageError> {
+      match inline.clone().or_else(|| args.next()) {
+        Some(value) => Ok(value),
+        None => Err(UsageError(format!("`{}` expects a value", name))),
+      }
+    };
+
+    match flag.as_str() {
+      "-h" | "--help" => return Ok(Command::Help),
+      "-q" | "--quiet" => render.quiet = true,
+      "-o" | "--output" => render.output = PathBuf::from(value(&flag)?),
+      "--width" => render.width = Some(integer(&flag, &value(&flag)?, 1, MAX_DIMENSION)?),
+      "--height" => render.height = Some(integer(&flag, &value(&flag)?, 1, MAX_DIMENSION)?),
+      "--spp" => render.samples = Some(integer(&flag, &value(&flag)?, 1, MAX_SAMPLES)?),
+      "--filter" => {
+        let name = value(&flag)?;
+        render.filter = Some(Filter::from_name(&name).ok_or_else(|| {
+          UsageError(format!("`{}` expects one of {}, got `{}`", flag, Filter::NAMES.join(", "), name))
+        })?);
+      },
+      "--threads" => render.threads = Some(integer(&flag, &value(&flag)?, 0, usize::MAX)?),
+      "--tile-size" => render.tile_size = Some(integer(&flag, &value(&flag)?, 1, MAX_DIMENSION)?),
+      "--exposure" => render.save.display.exposure = number(&flag, &value(&flag)?)?,
+      "--tonemap" => render.save.display.tone_mapper = match value(&flag)?.as_str() {
+        "clamp" => ToneMapper::Clamp,
+        "reinhard" => ToneMapper::Reinhard,
+        "reinhard-extended" => ToneMapper::ExtendedReinhard { white: DEFAULT_WHITE },
+        "aces" => ToneMapper::Aces,
+        "hable" => ToneMapper::Hable,
+        other => return Err(UsageError(format!(
+          "`{}` expects clamp, reinhard, reinhard-extended, aces or hable, got `{}`", flag, other,
+        ))),
+      },
+      "--white" => match number(&flag, &value(&flag)?)? {
+        w if w > 0.0 => white = Some(w),
+        _ => return Err(UsageError(format!("`{}` must be positive", flag))),
+      },
+      "--dither" => render.save.display.dither = match value(&flag)?.as_str() {
+        "none" => Dither::None,
+        "ordered" => Dither::Ordered,
+        "blue-noise" => Dither::BlueNoise,
+        other => return Err(UsageError(format!("`{}` expects none, ordered or blue-noise, got `{}`", flag, other))),
+      },
+      "--exr-compression" => render.save.exr_compression = match value(&flag)?.as_str() {
+        "none" => Compression::None,
+        "rle" => Compression::Rle,
+        "zip" => Compression::Zip,
+        other => return Err(UsageError(format!("`{}` expects none, rle or zip, got `{}`", flag, other))),
+      },
+      "--exr-float" => render.save.exr_beauty = PixelType::Float,
+      _ if flag.starts_with('-') && flag.len() > 1 => {
+        return Err(UsageError(format!("unknown option `{}`", flag)));
+      },
+      _ if scene.is_none() => scene = Some(PathBuf::from(arg)),
+      _ => return Err(UsageError(format!("unexpected argument `{}`", arg))),
+    }
+  }
+
+  render.scene = scene.ok_or_else(|| UsageError("missing scene file".to_string()))?;
+  // `--white` may come after `--tonemap`
+  if let Some(white) = white {
+    match render.save.display.tone_mapper {
+      ToneMapper::ExtendedReinhard { .. } => render.save.display.tone_mapper = ToneMapper::ExtendedReinhard { white },
+      _ => return Err(UsageError("`--white` only applies to `--tonemap reinhard-extended`".to_string())),
+    }
+  }
+  Ok(Command::Render(render))
+}
+
+fn integer(flag: &str, value: &str, min: usize, max: usize) -> Result<usize, UsageError> {
+  match value.parse::<usize>() {
+    Ok(n) if n >= min && n <= max => Ok(n),
+    Ok(_) if max == usize::MAX => Err(UsageError(format!("`{}` must be at least {}", flag, min))),
+    Ok(_) => Err(UsageError(format!("`{}` must be between {} and {}", flag, min, max))),
+    Err(_) => Err(UsageError(format!("`{}` expects a whole number, got `{}`", flag, value))),
+  }
+}
+
+fn number(flag: &str, value: &str) -> Result<f32, UsageError> {
+  match value.parse::<f32>() {
+    Ok(n) if n.is_finite() => Ok(n),
+    _ => Err(UsageError(format!("`{}` expects a number, got `{}`", flag, value))),
+  }
+}
@@ -0,0 +1,89 @@
+use std::ops::{Add, AddAssign, Index, Mul, Sub};
+
+/// A linear RGB color or amount of light. Unlike `Vector3D` its channels
+/// multiply component-wise, the way a surface filters the light hitting it.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Color {
+  pub r: f32,
+  pub g: f32,
+  pub b: f32,
+}
+
+impl Color {
+  pub const BLACK: Color = Color { r: 0.0, g: 0.0, b: 0.0 };
+  pub const WHITE: Color = Color { r: 1.0, g: 1.0, b: 1.0 };
+
+  pub fn new(r: f32, g: f32, b: f32) -> Color {
+    Color { r, g, b }
+  }
+
+  /// A grey with every channel set to `value`.
+  pub fn gray(value: f32) -> Color {
+    Color { r: value, g: value, b: value }
+  }
+
+  /// Relative luminance using the Rec. 709 primaries shared with sRGB.
+  pub fn luminance(&self) -> f32 {
+    0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+  }
+
+  /// Applies `f` to each channel.
+  pub fn map<F: Fn(f32) -> f32>(&self, f: F) -> Color {
+    Color { r: f(self.r), g: f(self.g), b: f(self.b) }
+  }
+
+  pub fn clamp(&self, min: f32, max: f32) -> Color {
+    self.map(|c| c.min(max).max(min))
+  }
+}
+
+impl Add for Color {
+  type Output = Color;
+
+  fn add(self, other: Color) -> Color {
+    Color { r: self.r + other.r, g: self.g + other.g, b: self.b + other.b }
+  }
+}
+
+impl AddAssign for Color {
+  fn add_assign(&mut self, other: Color) {
+    *self = *self + other;
+  }
+}
+
+impl Sub for Color {
+  type Output = Color;
+
+  fn sub(self, other: Color) -> Color {
+    Color { r: self.r - other.r, g: self.g - other.g, b: self.b - other.b }
+  }
+}
+
+impl Mul for Color {
+  type Output = Color;
+
+  fn mul(self, other: Color) -> Color {
+    Color { r: self.r * other.r, g: self.g * other.g, b: self.b * other.b }
+  }
+}
+
+impl Mul<f32> for Color {
+  type Output = Color;
+
+  fn mul(self, scalar: f32) -> Color {
+    Color { r: self.r * scalar, g: self.g * scalar, b: self.b * scalar }
+  }
+}
+
+impl Index<usize> for Color {
+  type Output = f32;
+
+  fn index(&self, channel: usize) -> &f32 {
+    match channel {
+      0 => &self.r,
+      1 => &self.g,
+      2 => &self.b,
+      _ => panic!("channel {} is out of range for a Color", channel),
+    }
+  }
+}
@@ -0,0 +1,53 @@
+use crate::bounding_box::BoundingBox;
+use crate::plane::PARALLEL_EPSILON;
+use crate::ray::Ray;
+use crate::shape::Shape;
+use crate::vector::Vector3D;
+
+/// A flat, round disc centered on `center` and facing `normal`.
+#[derive(Debug, Clone)]
+pub struct Disc {
+  pub center: Vector3D,
+  pub normal: Vector3D,
+  pub radius: f32,
+}
+
+impl Disc {
+  pub fn intersects(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<f32> {
+    let denom = self.normal.dot(&ray.direction);
+    if denom.abs() < PARALLEL_EPSILON { return None; }
+
+    let t = (self.center - ray.origin).dot(&self.normal) / denom;
+    if t <= t_min || t >= t_max { return None; }
+
+    let offset = ray.at(t) - self.center;
+    if offset.dot(&offset) > self.radius * self.radius { return None; }
+
+    Some(t)
+  }
+
+  pub fn surface_normal(&self, _hit_point: &Vector3D) -> Vector3D {
+    self.normal.normalize()
+  }
+}
+
+impl Shape for Disc {
+  fn intersect(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<f32> {
+    self.intersects(ray, t_min, t_max)
+  }
+
+  fn normal(&self, point: &Vector3D) -> Vector3D {
+    self.surface_normal(point)
+  }
+
+  fn bounding_box(&self) -> BoundingBox {
+    // the disc's extent along each axis shrinks as its normal turns towards it
+    let n = self.normal.normalize();
+    let extent = Vector3D::new(
+      self.radius * (1.0 - n.x * n.x).max(0.0).sqrt(),
+      self.radius * (1.0 - n.y * n.y).max(0.0).sqrt(),
+      self.radius * (1.0 - n.z * n.z).max(0.0).sqrt(),
+    );
+    BoundingBox::new(self.center - extent, self.center + extent)
+  }
+}
@@ -0,0 +1,108 @@
+use std::f32::consts::{PI, TAU};
+
+use crate::color::Color;
+use crate::image::Image;
+use crate::light::{Light, LightSample};
+use crate::sampling::Distribution2D;
+use crate::vector::Vector3D;
+
+/// Light arriving from every direction out of a latitude-longitude image
+/// wrapped around the scene. Rays that escape the scene see it, and shading
+/// points pick directions in proportion to its brightness so that small,
+/// bright features like the sun don't turn into noise.
+///
+/// The middle of the image lies along -z with +x to its right, the same
+/// layout the equirectangular camera renders. Like the area lights, a
+/// surface surrounded by an evenly lit map receives `intensity` times its
+/// pixel values as irradiance.
+#[derive(Debug, Clone)]
+pub struct EnvironmentLight {
+  /// Scales every pixel of the map.
+  pub intensity: f32,
+  /// Turns the map about the vertical axis, in degrees towards +x.
+  pub rotation: f32,
+  /// Number of directions sampled at every shaded point.
+  pub samples: usize,
+  image: Image,
+  /// Chooses pixels by their luminance, weighted by the solid angle they
+  /// cover.
+  distribution: Distribution2D,
+}
+
+impl EnvironmentLight {
+  pub fn new(image: Image, intensity: f32, rotation: f32, samples: usize) -> EnvironmentLight {
+    let (width, height) = (image.width, image.height);
+    let weights: Vec<f32> = image.pixels
+      .iter()
+      .enumerate()
+      .map(|(i, pixel)| {
+        // rows near the poles are squeezed into less of the sphere
+        let sin_theta = (((i / width) as f32 + 0.5) / height as f32 * PI).sin();
+        pixel.luminance().max(0.0) * sin_theta
+      })
+      .collect();
+    let distribution = Distribution2D::new(&weights, width, height);
+
+    EnvironmentLight { intensity, rotation, samples, image, distribution }
+  }
+
+  pub fn image(&self) -> &Image {
+    &self.image
+  }
+
+  /// Where `direction` points to on the map, from `(0, 0)` at its top left
+  /// corner to `(1, 1)` at its bottom right.
+  pub fn uv(&self, direction: Vector3D) -> (f32, f32) {
+    let direction = direction.normalize();
+    let longitude = direction.x.atan2(-direction.z) - self.rotation.to_radians();
+    let u = (longitude / TAU + 0.5).rem_euclid(1.0);
+    let v = direction.y.clamp(-1.0, 1.0).acos() / PI;
+    (u, v)
+  }
+
+  /// The unit direction pointing at `(u, v)` on the map.
+  pub fn direction(&self, (u, v): (f32, f32)) -> Vector3D {
+    lat_long_direction((u + self.rotation / 360.0, v))
+  }
+
+  /// The pixel covering `(u, v)`, scaled by the intensity.
+  fn lookup(&self, (u, v): (f32, f32)) -> Color {
+    if self.image.pixels.is_empty() { return Color::BLACK; }
+    let x = ((u * self.image.width as f32) as usize).min(self.image.width - 1);
+    let y = ((v * self.image.height as f32) as usize).min(self.image.height - 1);
+    self.image.pixel(x, y) * self.intensity
+  }
+}
+
+/// The unit direction pointing at `(u, v)` on an unrotated
+/// latitude-longitude map.
+pub fn lat_long_direction((u, v): (f32, f32)) -> Vector3D {
+  let longitude = (u - 0.5) * TAU;
+  let latitude = (0.5 - v) * PI;
+  Vector3D::new(longitude.sin() * latitude.cos(), latitude.sin(), -longitude.cos() * latitude.cos())
+}
+
+impl Light for EnvironmentLight {
+  fn sample(&self, _point: Vector3D, u: (f32, f32)) -> Option<LightSample> {
+    let (uv, pdf) = self.distribution.sample(u)?;
+    let sin_theta = (uv.1 * PI).sin();
+    if pdf <= 0.0 || sin_theta <= 0.0 { return None; }
+
+    // the map covers 2π by π radians, each squeezed by sin θ towards the
+    // poles
+    let solid_angle_pdf = pdf / (2.0 * PI * PI * sin_theta);
+    Some(LightSample {
+      direction: self.direction(uv),
+      distance: f32::INFINITY,
+      irradiance: self.lookup(uv) * (1.0 / (PI * solid_angle_pdf)),
+    })
+  }
+
+  fn samples(&self) -> usize {
+    self.samples
+  }
+
+  fn environment(&self, direction: Vector3D) -> Color {
+    self.lookup(self.uv(direction))
+  }
+}
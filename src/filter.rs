@@ -0,0 +1,87 @@
+use std::f32::consts::PI;
+
+/// Reconstruction filters weighing how much each sample counts towards the
+/// pixels around it. They are separable: the weight of a sample is the
+/// product of the filter at its horizontal and vertical offsets from a
+/// pixel's center.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Filter {
+  /// Every sample counts fully towards the pixel it lands in, and nothing
+  /// else.
+  #[default]
+  Box,
+  /// Weights falling off linearly to zero one pixel away.
+  Tent,
+  /// A Gaussian cut off 1.5 pixels away, soft but free of ringing.
+  Gaussian,
+  /// The Mitchell-Netravali cubic with B = C = 1/3, a sharp compromise
+  /// between blurring and ringing.
+  Mitchell,
+  /// A three lobed windowed sinc, the sharpest but prone to halos around
+  /// bright edges.
+  Lanczos,
+}
+
+impl Filter {
+  pub const NAMES: &'static [&'static str] = &["box", "tent", "gaussian", "mitchell", "lanczos"];
+
+  /// Looks a filter up by its lowercase name.
+  pub fn from_name(name: &str) -> Option<Filter> {
+    match name {
+      "box" => Some(Filter::Box),
+      "tent" => Some(Filter::Tent),
+      "gaussian" => Some(Filter::Gaussian),
+      "mitchell" => Some(Filter::Mitchell),
+      "lanczos" => Some(Filter::Lanczos),
+      _ => None,
+    }
+  }
+
+  /// Distance in pixels beyond which the filter is zero.
+  pub fn radius(self) -> f32 {
+    match self {
+      Filter::Box => 0.5,
+      Filter::Tent => 1.0,
+      Filter::Gaussian => 1.5,
+      Filter::Mitchell => 2.0,
+      Filter::Lanczos => 3.0,
+    }
+  }
+
+  /// The one dimensional filter at offset `x` pixels.
+  pub fn evaluate(self, x: f32) -> f32 {
+    match (self, x.abs()) {
+      // half open, so samples on the border between two pixels only count
+      // towards one of them
+      (Filter::Box, _) => if (-0.5..0.5).contains(&x) { 1.0 } else { 0.0 },
+      (_, x) if x >= self.radius() => 0.0,
+      (Filter::Tent, x) => 1.0 - x,
+      (Filter::Gaussian, x) => {
+        // shifted down so it reaches zero at the radius instead of jumping
+        const ALPHA: f32 = 2.0;
+        (-ALPHA * x * x).exp() - (-ALPHA * 1.5 * 1.5f32).exp()
+      },
+      (Filter::Mitchell, x) => {
+        const B: f32 = 1.0 / 3.0;
+        const C: f32 = 1.0 / 3.0;
+        let polynomial = if x < 1.0 {
+          (12.0 - 9.0 * B - 6.0 * C) * x * x * x + (-18.0 + 12.0 * B + 6.0 * C) * x * x + (6.0 - 2.0 * B)
+        } else {
+          (-B - 6.0 * C) * x * x * x + (6.0 * B + 30.0 * C) * x * x + (-12.0 * B - 48.0 * C) * x + (8.0 * B + 24.0 * C)
+        };
+        polynomial / 6.0
+      },
+      (Filter::Lanczos, x) => sinc(x) * sinc(x / 3.0),
+    }
+  }
+
+  /// The weight of a sample at offset `(dx, dy)` from a pixel's center.
+  pub fn weight(self, dx: f32, dy: f32) -> f32 {
+    self.evaluate(dx) * self.evaluate(dy)
+  }
+}
+
+fn sinc(x: f32) -> f32 {
+  if x < 1e-5 { return 1.0; }
+  (PI * x).sin() / (PI * x)
+}
@@ -0,0 +1,329 @@
+//! A compact DEFLATE (RFC 1951) encoder wrapped in a zlib (RFC 1950) stream.
+//!
+//! Input is matched against a 32 KiB sliding window with hash chains, and
+//! every block is emitted either stored or with the fixed Huffman codes,
+//! whichever is smaller. There are no dynamic Huffman tables.
+
+const WINDOW_SIZE: usize = 1 << 15;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const HASH_BITS: u32 = 15;
+/// How many earlier positions with the same hash are tried per match.
+const MAX_CHAIN: usize = 128;
+/// Tokens gathered before deciding how to encode a block.
+const BLOCK_TOKENS: usize = 1 << 16;
+const MAX_STORED: usize = 65535;
+
+/// How hard `zlib_compress` tries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+  /// Only stored blocks, which copy the input verbatim.
+  Stored,
+  /// LZ77 matching with fixed Huffman codes, falling back to stored blocks
+  /// for data that doesn't compress.
+  Fixed,
+}
+
+const LENGTH_BASE: [u16; 29] = [
+  3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31,
+  35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+  0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2,
+  3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+  1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193,
+  257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+  0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6,
+  7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];
+
+#[derive(Debug, Clone, Copy)]
+enum Token {
+  Literal(u8),
+  Match { length: u16, distance: u16 },
+}
+
+/// Compresses `data` into a complete zlib stream.
+pub fn zlib_compress(data: &[u8], compression: Compression) -> Vec<u8> {
+  // 32K window, deflate, no preset dictionary; FLG makes the header a
+  // multiple of 31
+  let mut out = vec![0x78, 0x01];
+  out.extend_from_slice(&deflate(data, compression));
+  out.extend_from_slice(&adler32(data).to_be_bytes());
+  out
+}
+
+/// Compresses `data` into a raw DEFLATE stream.
+pub fn deflate(data: &[u8], compression: Compression) -> Vec<u8> {
+  let mut writer = BitWriter::new();
+
+  match compression {
+    Compression::Stored => write_stored(&mut writer, data, true),
+    Compression::Fixed => {
+      let mut matcher = Matcher::new(data);
+      let mut start = 0;
+      loop {
+        let (tokens, end) = matcher.tokens(start, BLOCK_TOKENS);
+        let last = end == data.len();
+
+        if fixed_cost(&tokens) < stored_cost(end - start) {
+          write_fixed(&mut writer, &tokens, last);
+        } else {
+          write_stored(&mut writer, &data[start..end], last);
+        }
+
+        if last { break; }
+        start = end;
+      }
+    },
+  }
+
+  writer.finish()
+}
+
+/// The Adler-32 checksum zlib streams end with.
+pub fn adler32(data: &[u8]) -> u32 {
+  const MOD: u32 = 65521;
+  let (mut a, mut b) = (1u32, 0u32);
+
+  // 5552 is the most bytes that can be summed before `b` could overflow
+  for chunk in data.chunks(5552) {
+    for &byte in chunk {
+      a += byte as u32;
+      b += a;
+    }
+    a %= MOD;
+    b %= MOD;
+  }
+
+  (b << 16) | a
+}
+
+struct BitWriter {
+  bytes: Vec<u8>,
+  buffer: u64,
+  count: u32,
+}
+
+impl BitWriter {
+  fn new() -> BitWriter {
+    BitWriter { bytes: Vec::new(), buffer: 0, count: 0 }
+  }
+
+  /// Appends the low `count` bits of `value`, least significant first.
+  fn bits(&mut self, value: u32, count: u32) {
+    self.buffer |= (value as u64) << self.count;
+    self.count += count;
+    while self.count >= 8 {
+      self.bytes.push(self.buffer as u8);
+      self.buffer >>= 8;
+      self.count -= 8;
+    }
+  }
+
+  /// Appends a Huffman code, which DEFLATE stores most significant bit first.
+  fn code(&mut self, code: u32, length: u32) {
+    self.bits(code.reverse_bits() >> (32 - length), length);
+  }
+
+  fn align(&mut self) {
+    if self.count > 0 {
+      self.bits(0, 8 - self.count);
+    }
+  }
+
+  fn finish(mut self) -> Vec<u8> {
+    self.align();
+    self.bytes
+  }
+}
+
+fn write_stored(writer: &mut BitWriter, data: &[u8], last: bool) {
+  let mut chunks = data.chunks(MAX_STORED).peekable();
+  if chunks.peek().is_none() {
+    // an empty input still needs one (empty) final block
+    writer.bits(last as u32, 1);
+    writer.bits(0, 2);
+    writer.align();
+    writer.bits(0x0000, 16);
+    writer.bits(0xffff, 16);
+    return;
+  }
+
+  while let Some(chunk) = chunks.next() {
+    let final_block = last && chunks.peek().is_none();
+    writer.bits(final_block as u32, 1);
+    writer.bits(0, 2);
+    writer.align();
+    writer.bits(chunk.len() as u32, 16);
+    writer.bits(!(chunk.len() as u32) & 0xffff, 16);
+    for &byte in chunk {
+      writer.bits(byte as u32, 8);
+    }
+  }
+}
+
+fn write_fixed(writer: &mut BitWriter, tokens: &[Token], last: bool) {
+  writer.bits(last as u32, 1);
+  writer.bits(1, 2);
+
+  for token in tokens {
+    match *token {
+      Token::Literal(byte) => literal_code(writer, byte as u16),
+      Token::Match { length, distance } => {
+        let l = length_symbol(length);
+        literal_code(writer, 257 + l as u16);
+        writer.bits((length - LENGTH_BASE[l]) as u32, LENGTH_EXTRA[l] as u32);
+
+        let d = distance_symbol(distance);
+        writer.code(d as u32, 5);
+        writer.bits((distance - DISTANCE_BASE[d]) as u32, DISTANCE_EXTRA[d] as u32);
+      },
+    }
+  }
+
+  literal_code(writer, 256);
+}
+
+/// Writes a literal/length symbol with the fixed Huffman code.
+fn literal_code(writer: &mut BitWriter, symbol: u16) {
+  let (code, length) = fixed_literal_code(symbol);
+  writer.code(code, length);
+}
+
+fn fixed_literal_code(symbol: u16) -> (u32, u32) {
+  let symbol = symbol as u32;
+  match symbol {
+    0..=143 => (0x30 + symbol, 8),
+    144..=255 => (0x190 + symbol - 144, 9),
+    256..=279 => (symbol - 256, 7),
+    _ => (0xc0 + symbol - 280, 8),
+  }
+}
+
+fn length_symbol(length: u16) -> usize {
+  match LENGTH_BASE.binary_search(&length) {
+    Ok(i) => i,
+    Err(i) => i - 1,
+  }
+}
+
+fn distance_symbol(distance: u16) -> usize {
+  match DISTANCE_BASE.binary_search(&distance) {
+    Ok(i) => i,
+    Err(i) => i - 1,
+  }
+}
+
+/// Size in bits of `tokens` as a fixed Huffman block.
+fn fixed_cost(tokens: &[Token]) -> usize {
+  let body: usize = tokens.iter().map(|token| match *token {
+    Token::Literal(byte) => fixed_literal_code(byte as u16).1 as usize,
+    Token::Match { length, distance } => {
+      let l = length_symbol(length);
+      let d = distance_symbol(distance);
+      fixed_literal_code(257 + l as u16).1 as usize + LENGTH_EXTRA[l] as usize + 5 + DISTANCE_EXTRA[d] as usize
+    },
+  }).sum();
+  3 + body + 7
+}
+
+/// Size in bits of `length` bytes as stored blocks, including the worst case
+/// for byte alignment.
+fn stored_cost(length: usize) -> usize {
+  let blocks = length.div_ceil(MAX_STORED).max(1);
+  blocks * (3 + 7 + 32) + length * 8
+}
+
+/// Finds repeated strings with hash chains over a sliding window.
+struct Matcher<'a> {
+  data: &'a [u8],
+  head: Vec<u32>,
+  prev: Vec<u32>,
+}
+
+/// Marks an empty hash chain entry.
+const NONE: u32 = u32::MAX;
+
+impl<'a> Matcher<'a> {
+  fn new(data: &'a [u8]) -> Matcher<'a> {
+    Matcher { data, head: vec![NONE; 1 << HASH_BITS], prev: vec![NONE; WINDOW_SIZE] }
+  }
+
+  fn hash(&self, position: usize) -> usize {
+    let d = self.data;
+    let key = (d[position] as u32) << 16 | (d[position + 1] as u32) << 8 | d[position + 2] as u32;
+    (key.wrapping_mul(2654435761) >> (32 - HASH_BITS)) as usize
+  }
+
+  fn insert(&mut self, position: usize) {
+    if position + MIN_MATCH > self.data.len() { return; }
+    let hash = self.hash(position);
+    self.prev[position % WINDOW_SIZE] = self.head[hash];
+    self.head[hash] = position as u32;
+  }
+
+  /// The longest earlier occurrence of the data at `position`.
+  fn longest_match(&self, position: usize) -> Option<(usize, usize)> {
+    if position + MIN_MATCH > self.data.len() { return None; }
+
+    let max_length = MAX_MATCH.min(self.data.len() - position);
+    let mut best: Option<(usize, usize)> = None;
+    let mut candidate = self.head[self.hash(position)];
+    let mut chain = 0;
+
+    while candidate != NONE && chain < MAX_CHAIN {
+      let start = candidate as usize;
+      let distance = position - start;
+      if distance > WINDOW_SIZE - 1 { break; }
+
+      let length = self.data[start..start + max_length].iter()
+        .zip(&self.data[position..position + max_length])
+        .take_while(|(a, b)| a == b)
+        .count();
+
+      if length >= MIN_MATCH && best.is_none_or(|(best_length, _)| length > best_length) {
+        best = Some((length, distance));
+        if length == max_length { break; }
+      }
+
+      let next = self.prev[start % WINDOW_SIZE];
+      // entries older than the window have been overwritten by newer ones
+      if next == NONE || next as usize >= start { break; }
+      candidate = next;
+      chain += 1;
+    }
+
+    best
+  }
+
+  /// Tokenizes from `start` until `limit` tokens or the end of the data,
+  /// returning the tokens and the position they end at.
+  fn tokens(&mut self, start: usize, limit: usize) -> (Vec<Token>, usize) {
+    let mut tokens = Vec::with_capacity(limit.min(self.data.len() - start));
+    let mut position = start;
+
+    while position < self.data.len() && tokens.len() < limit {
+      match self.longest_match(position) {
+        Some((length, distance)) => {
+          tokens.push(Token::Match { length: length as u16, distance: distance as u16 });
+          for p in position..position + length {
+            self.insert(p);
+          }
+          position += length;
+        },
+        None => {
+          tokens.push(Token::Literal(self.data[position]));
+          self.insert(position);
+          position += 1;
+        },
+      }
+    }
+
+    (tokens, position)
+  }
+}
@@ -0,0 +1,292 @@
+//! An OpenEXR writer for single part scanline images.
+//!
+//! Channels can be stored as 16-bit half or 32-bit float, either
+//! uncompressed or with the lossless RLE or ZIP schemes. Channels named like
+//! `layer.X` are grouped into layers by EXR readers.
+
+use std::io::{self, Write};
+
+use crate::format::deflate::{zlib_compress, Compression as Deflate};
+
+const MAGIC: [u8; 4] = [0x76, 0x2f, 0x31, 0x01];
+/// Format version 2 with no feature flags: single part, scanlines, short names.
+const VERSION: [u8; 4] = [2, 0, 0, 0];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelType {
+  Half,
+  Float,
+}
+
+impl PixelType {
+  fn code(self) -> i32 {
+    match self {
+      PixelType::Half => 1,
+      PixelType::Float => 2,
+    }
+  }
+
+  fn size(self) -> usize {
+    match self {
+      PixelType::Half => 2,
+      PixelType::Float => 4,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+  None,
+  /// Run-length encoding, one scanline per block.
+  Rle,
+  /// zlib compression, sixteen scanlines per block.
+  Zip,
+}
+
+impl Compression {
+  fn code(self) -> u8 {
+    match self {
+      Compression::None => 0,
+      Compression::Rle => 1,
+      Compression::Zip => 3,
+    }
+  }
+
+  fn lines_per_block(self) -> usize {
+    match self {
+      Compression::Zip => 16,
+      _ => 1,
+    }
+  }
+}
+
+/// One channel of an image, one sample per pixel from the top left corner.
+#[derive(Debug, Clone)]
+pub struct Channel<'a> {
+  pub name: String,
+  pub pixel_type: PixelType,
+  pub samples: &'a [f32],
+}
+
+/// Writes `channels` as an OpenEXR image.
+pub fn write_exr<W: Write>(
+  out: &mut W,
+  width: usize,
+  height: usize,
+  channels: &[Channel],
+  compression: Compression,
+) -> io::Result<()> {
+  if width == 0 || height == 0 || width > i32::MAX as usize || height > i32::MAX as usize {
+    return Err(io::Error::new(io::ErrorKind::InvalidInput, "EXR dimensions must be between 1 and 2^31 - 1"));
+  }
+  if let Some(channel) = channels.iter().find(|c| c.samples.len() != width * height) {
+    return Err(io::Error::new(
+      io::ErrorKind::InvalidInput,
+      format!("channel `{}` does not have one sample per pixel", channel.name),
+    ));
+  }
+
+  // readers expect channels in alphabetical order, both in the header and
+  // in the pixel data
+  let mut channels: Vec<&Channel> = channels.iter().collect();
+  channels.sort_by(|a, b| a.name.as_bytes().cmp(b.name.as_bytes()));
+  if channels.windows(2).any(|pair| pair[0].name == pair[1].name) {
+    return Err(io::Error::new(io::ErrorKind::InvalidInput, "EXR channel names must be unique"));
+  }
+
+  let header = header(width, height, &channels, compression);
+
+  let lines = compression.lines_per_block();
+  let blocks: Vec<Vec<u8>> = (0..height)
+    .step_by(lines)
+    .map(|y| block(width, y, lines.min(height - y), &channels, compression))
+    .collect();
+
+  out.write_all(&MAGIC)?;
+  out.write_all(&VERSION)?;
+  out.write_all(&header)?;
+
+  // the offset table points at every block from the start of the file
+  let mut offset = (MAGIC.len() + VERSION.len() + header.len() + blocks.len() * 8) as u64;
+  for block in &blocks {
+    out.write_all(&offset.to_le_bytes())?;
+    offset += block.len() as u64;
+  }
+
+  for block in &blocks {
+    out.write_all(block)?;
+  }
+
+  Ok(())
+}
+
+fn header(width: usize, height: usize, channels: &[&Channel], compression: Compression) -> Vec<u8> {
+  let mut header = Vec::new();
+
+  let mut list = Vec::new();
+  for channel in channels {
+    list.extend_from_slice(channel.name.as_bytes());
+    list.push(0);
+    list.extend_from_slice(&channel.pixel_type.code().to_le_bytes());
+    // pLinear and three reserved bytes, then x and y sampling
+    list.extend_from_slice(&[0, 0, 0, 0]);
+    list.extend_from_slice(&1i32.to_le_bytes());
+    list.extend_from_slice(&1i32.to_le_bytes());
+  }
+  list.push(0);
+  attribute(&mut header, "channels", "chlist", &list);
+
+  attribute(&mut header, "compression", "compression", &[compression.code()]);
+
+  let mut window = Vec::new();
+  for value in &[0, 0, width as i32 - 1, height as i32 - 1] {
+    window.extend_from_slice(&value.to_le_bytes());
+  }
+  attribute(&mut header, "dataWindow", "box2i", &window);
+  attribute(&mut header, "displayWindow", "box2i", &window);
+
+  // increasing y
+  attribute(&mut header, "lineOrder", "lineOrder", &[0]);
+  attribute(&mut header, "pixelAspectRatio", "float", &1f32.to_le_bytes());
+  attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8]);
+  attribute(&mut header, "screenWindowWidth", "float", &1f32.to_le_bytes());
+
+  header.push(0);
+  header
+}
+
+fn attribute(header: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+  header.extend_from_slice(name.as_bytes());
+  header.push(0);
+  header.extend_from_slice(kind.as_bytes());
+  header.push(0);
+  header.extend_from_slice(&(value.len() as i32).to_le_bytes());
+  header.extend_from_slice(value);
+}
+
+/// Encodes `lines` scanlines starting at `y` as one chunk: its first line,
+/// the size of its data and the data itself.
+fn block(width: usize, y: usize, lines: usize, channels: &[&Channel], compression: Compression) -> Vec<u8> {
+  let mut raw = Vec::new();
+  for line in y..y + lines {
+    for channel in channels {
+      for &sample in &channel.samples[line * width..(line + 1) * width] {
+        match channel.pixel_type {
+          PixelType::Half => raw.extend_from_slice(&f32_to_half(sample).to_le_bytes()),
+          PixelType::Float => raw.extend_from_slice(&sample.to_le_bytes()),
+        }
+      }
+    }
+  }
+  debug_assert_eq!(raw.len(), lines * width * channels.iter().map(|c| c.pixel_type.size()).sum::<usize>());
+
+  let data = match compression {
+    Compression::None => raw,
+    Compression::Rle => smaller(run_length_encode(&predict(&raw)), raw),
+    Compression::Zip => smaller(zlib_compress(&predict(&raw), Deflate::Fixed), raw),
+  };
+
+  let mut block = Vec::with_capacity(data.len() + 8);
+  block.extend_from_slice(&(y as i32).to_le_bytes());
+  block.extend_from_slice(&(data.len() as i32).to_le_bytes());
+  block.extend_from_slice(&data);
+  block
+}
+
+/// Blocks that don't shrink are stored raw, which readers recognise by the
+/// data being exactly the uncompressed size.
+fn smaller(compressed: Vec<u8>, raw: Vec<u8>) -> Vec<u8> {
+  if compressed.len() < raw.len() { compressed } else { raw }
+}
+
+/// The byte reordering and delta predictor the RLE and ZIP schemes apply
+/// before compressing: even bytes then odd bytes, each stored as the
+/// difference from the one before.
+fn predict(raw: &[u8]) -> Vec<u8> {
+  let half = raw.len().div_ceil(2);
+  let mut reordered = vec![0; raw.len()];
+  for (i, &byte) in raw.iter().enumerate() {
+    let j = if i % 2 == 0 { i / 2 } else { half + i / 2 };
+    reordered[j] = byte;
+  }
+
+  let mut previous = reordered.first().copied().unwrap_or(0);
+  for byte in reordered.iter_mut().skip(1) {
+    let current = *byte;
+    *byte = current.wrapping_sub(previous).wrapping_add(128);
+    previous = current;
+  }
+  reordered
+}
+
+/// OpenEXR's byte-wise run-length encoding: a non-negative count `n` is
+/// followed by one byte repeated `n + 1` times, a negative count `-n` by
+/// `n` literal bytes.
+fn run_length_encode(data: &[u8]) -> Vec<u8> {
+  const MAX_RUN: usize = 127;
+  const MIN_RUN: usize = 3;
+  let mut out = Vec::new();
+  let mut start = 0;
+
+  while start < data.len() {
+    let mut end = start + 1;
+    while end < data.len() && data[end] == data[start] && end - start - 1 < MAX_RUN {
+      end += 1;
+    }
+
+    if end - start >= MIN_RUN {
+      out.push((end - start - 1) as u8);
+      out.push(data[start]);
+    } else {
+      // extend the literal span until the next run of three starts
+      while end < data.len()
+        && !(end + 2 < data.len() && data[end] == data[end + 1] && data[end + 1] == data[end + 2])
+        && end - start < MAX_RUN {
+        end += 1;
+      }
+      out.push((-((end - start) as i32)) as i8 as u8);
+      out.extend_from_slice(&data[start..end]);
+    }
+
+    start = end;
+  }
+
+  out
+}
+
+/// Converts to IEEE 754 half precision, rounding to nearest even. Values
+/// beyond the half range become infinity.
+pub fn f32_to_half(value: f32) -> u16 {
+  let bits = value.to_bits();
+  let sign = ((bits >> 16) & 0x8000) as u16;
+  let exponent = ((bits >> 23) & 0xff) as i32;
+  let mantissa = bits & 0x7f_ffff;
+
+  if exponent == 0xff {
+    // infinity, or NaN with a mantissa bit kept set
+    return sign | 0x7c00 | if mantissa != 0 { 0x200 } else { 0 };
+  }
+
+  let half_exponent = exponent - 127 + 15;
+  if half_exponent >= 0x1f {
+    return sign | 0x7c00;
+  }
+
+  if half_exponent <= 0 {
+    // subnormal half, or too small even for that
+    if half_exponent < -10 { return sign; }
+    let mantissa = mantissa | 0x80_0000;
+    let shift = (14 - half_exponent) as u32;
+    let half = mantissa >> shift;
+    let remainder = mantissa & ((1 << shift) - 1);
+    let halfway = 1 << (shift - 1);
+    let round = remainder > halfway || (remainder == halfway && half & 1 != 0);
+    return sign | (half + round as u32) as u16;
+  }
+
+  let half = ((half_exponent as u32) << 10) | (mantissa >> 13);
+  let remainder = mantissa & 0x1fff;
+  let round = remainder > 0x1000 || (remainder == 0x1000 && half & 1 != 0);
+  // a carry out of the mantissa correctly bumps the exponent, up to infinity
+  sign | (half + round as u32) as u16
+}
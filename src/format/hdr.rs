@@ -0,0 +1,215 @@
+//! Radiance RGBE (`.hdr`) images, which store a shared 8-bit exponent with
+//! 8-bit mantissas for each channel.
+
+use std::io::{self, Read, Write};
+
+use crate::color::Color;
+
+/// Scanlines outside this range can't be run-length encoded.
+const RLE_WIDTHS: std::ops::Range<usize> = 8..32768;
+/// Shortest run of equal bytes worth encoding as a run.
+const MIN_RUN: usize = 4;
+
+/// Writes linear RGB pixels, top row first, as a run-length encoded
+/// Radiance picture.
+pub fn write_hdr<W: Write>(out: &mut W, width: usize, height: usize, pixels: &[Color]) -> io::Result<()> {
+  out.write_fmt(format_args!("#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n", height, width))?;
+
+  let mut rgbe = Vec::with_capacity(width);
+  let mut scanline = Vec::with_capacity(width * 4);
+  for row in pixels.chunks(width.max(1)).take(height) {
+    rgbe.clear();
+    rgbe.extend(row.iter().map(to_rgbe));
+
+    scanline.clear();
+    if RLE_WIDTHS.contains(&width) {
+      encode_scanline(&rgbe, &mut scanline);
+    } else {
+      scanline.extend(rgbe.iter().flatten());
+    }
+    out.write_all(&scanline)?;
+  }
+
+  Ok(())
+}
+
+/// Converts a linear color to a shared exponent representation.
+pub fn to_rgbe(color: &Color) -> [u8; 4] {
+  let v = color.r.max(color.g).max(color.b);
+  if v.is_nan() || v < 1e-32 { return [0, 0, 0, 0]; }
+  if v >= 2f32.powi(127) {
+    // beyond the largest exponent, infinities included, saturate at it
+    let channel = |c: f32| (c.max(0.0) * 2f32.powi(8 - 127)).min(255.0) as u8;
+    return [channel(color.r), channel(color.g), channel(color.b), 255];
+  }
+
+  let (mantissa, exponent) = frexp(v);
+  let scale = mantissa * 256.0 / v;
+  let channel = |c: f32| (c.max(0.0) * scale).min(255.0) as u8;
+  [channel(color.r), channel(color.g), channel(color.b), (exponent + 128).clamp(0, 255) as u8]
+}
+
+/// Converts a shared exponent pixel back to a linear color, taking each
+/// mantissa from the middle of the range it was rounded down from.
+pub fn from_rgbe(rgbe: [u8; 4]) -> Color {
+  if rgbe[3] == 0 { return Color::BLACK; }
+  let scale = 2f32.powi(rgbe[3] as i32 - (128 + 8));
+  Color::new((rgbe[0] as f32 + 0.5) * scale, (rgbe[1] as f32 + 0.5) * scale, (rgbe[2] as f32 + 0.5) * scale)
+}
+
+/// Reads a Radiance picture stored top row first, returning its width,
+/// height and linear RGB pixels. Scanlines may be flat or use the "new" RLE
+/// format.
+pub fn read_hdr<R: Read>(input: &mut R) -> io::Result<(usize, usize, Vec<Color>)> {
+  let mut data = Vec::new();
+  input.read_to_end(&mut data)?;
+
+  // the header is a list of lines ending with a blank one, followed by the
+  // resolution on a line of its own
+  let header_end = data.windows(2).position(|w| w == b"\n\n").ok_or_else(|| invalid("the header never ends"))? + 2;
+  let header = String::from_utf8_lossy(&data[..header_end]);
+  if !header.starts_with("#?") {
+    return Err(invalid("not a Radiance picture"));
+  }
+  for line in header.lines() {
+    if let Some(format) = line.strip_prefix("FORMAT=") {
+      if format != "32-bit_rle_rgbe" {
+        return Err(invalid(&format!("unsupported pixel format `{}`", format)));
+      }
+    }
+  }
+
+  let resolution_end = data[header_end..].iter().position(|&b| b == b'\n').ok_or_else(|| invalid("missing resolution"))?;
+  let resolution = String::from_utf8_lossy(&data[header_end..header_end + resolution_end]);
+  let (width, height) = match resolution.split_whitespace().collect::<Vec<_>>()[..] {
+    ["-Y", height, "+X", width] => match (width.parse::<usize>(), height.parse::<usize>()) {
+      (Ok(width), Ok(height)) => (width, height),
+      _ => return Err(invalid(&format!("bad resolution `{}`", resolution))),
+    },
+    _ => return Err(invalid(&format!("unsupported orientation `{}`", resolution))),
+  };
+
+  let mut data = &data[header_end + resolution_end + 1..];
+  // check the resolution against the data before allocating for it
+  let smallest = width.checked_mul(height).and_then(|_| smallest_size(width, height))
+    .ok_or_else(|| invalid(&format!("resolution `{}` is too large", resolution)))?;
+  if smallest > data.len() {
+    return Err(invalid("the pixel data is truncated"));
+  }
+
+  let mut pixels = Vec::with_capacity(width * height);
+  let mut scanline = vec![[0u8; 4]; width];
+  for _ in 0..if width > 0 { height } else { 0 } {
+    data = decode_scanline(data, &mut scanline)?;
+    pixels.extend(scanline.iter().map(|&rgbe| from_rgbe(rgbe)));
+  }
+
+  Ok((width, height, pixels))
+}
+
+/// The fewest bytes `height` scanlines of `width` pixels can be stored in,
+/// with every run as long as it can be, or `None` if that overflows.
+fn smallest_size(width: usize, height: usize) -> Option<usize> {
+  let scanline = if RLE_WIDTHS.contains(&width) { 4 + 4 * 2 * width.div_ceil(127) } else { width.checked_mul(4)? };
+  scanline.checked_mul(height)
+}
+
+/// Fills `scanline` from the start of `data`, returning what follows it.
+fn decode_scanline<'a>(data: &'a [u8], scanline: &mut [[u8; 4]]) -> io::Result<&'a [u8]> {
+  let width = scanline.len();
+  let truncated = || invalid("the pixel data is truncated");
+  let rle = RLE_WIDTHS.contains(&width) && data.len() >= 4 && data[0] == 2 && data[1] == 2 && data[2] & 0x80 == 0;
+  if !rle {
+    let bytes = data.get(..width * 4).ok_or_else(truncated)?;
+    for (pixel, rgbe) in scanline.iter_mut().zip(bytes.chunks(4)) {
+      pixel.copy_from_slice(rgbe);
+    }
+    return Ok(&data[width * 4..]);
+  }
+
+  if ((data[2] as usize) << 8 | data[3] as usize) != width {
+    return Err(invalid("a scanline has the wrong width"));
+  }
+  let mut position = 4;
+  for c in 0..4 {
+    let mut x = 0;
+    while x < width {
+      let count = *data.get(position).ok_or_else(truncated)? as usize;
+      position += 1;
+      if count > 128 {
+        let value = *data.get(position).ok_or_else(truncated)?;
+        position += 1;
+        for pixel in scanline.get_mut(x..x + count - 128).ok_or_else(|| invalid("a run overflows its scanline"))? {
+          pixel[c] = value;
+        }
+        x += count - 128;
+      } else {
+        if count == 0 { return Err(invalid("empty literal span")); }
+        let values = data.get(position..position + count).ok_or_else(truncated)?;
+        position += count;
+        let pixels = scanline.get_mut(x..x + count).ok_or_else(|| invalid("a span overflows its scanline"))?;
+        for (pixel, &value) in pixels.iter_mut().zip(values) {
+          pixel[c] = value;
+        }
+        x += count;
+      }
+    }
+  }
+  Ok(&data[position..])
+}
+
+fn invalid(message: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// Splits `v` into a mantissa in `[0.5, 1)` and a power of two.
+fn frexp(v: f32) -> (f32, i32) {
+  let mut exponent = v.log2().floor() as i32 + 1;
+  let mut mantissa = v / 2f32.powi(exponent);
+  // log2 can land one off near powers of two
+  if mantissa >= 1.0 {
+    mantissa *= 0.5;
+    exponent += 1;
+  } else if mantissa < 0.5 {
+    mantissa *= 2.0;
+    exponent -= 1;
+  }
+  (mantissa, exponent)
+}
+
+/// Encodes one scanline in the "new" RLE format: a marker followed by each
+/// of the four channels separately as runs and literal spans.
+fn encode_scanline(rgbe: &[[u8; 4]], out: &mut Vec<u8>) {
+  let width = rgbe.len();
+  out.extend_from_slice(&[2, 2, (width >> 8) as u8, (width & 0xff) as u8]);
+
+  let mut channel = Vec::with_capacity(width);
+  for c in 0..4 {
+    channel.clear();
+    channel.extend(rgbe.iter().map(|p| p[c]));
+
+    let mut i = 0;
+    while i < width {
+      let run = run_length(&channel[i..]);
+      if run >= MIN_RUN {
+        out.push(128 + run as u8);
+        out.push(channel[i]);
+        i += run;
+        continue;
+      }
+
+      // gather literals up to the next worthwhile run
+      let start = i;
+      while i < width && i - start < 128 && run_length(&channel[i..]) < MIN_RUN {
+        i += 1;
+      }
+      out.push((i - start) as u8);
+      out.extend_from_slice(&channel[start..i]);
+    }
+  }
+}
+
+/// Number of leading bytes equal to the first, at most 127.
+fn run_length(bytes: &[u8]) -> usize {
+  bytes.iter().take(127).take_while(|&&b| b == bytes[0]).count()
+}
@@ -0,0 +1,9 @@
+//! Encoders for the image file formats renders can be saved as, and
+//! decoders for the floating point ones environment maps are loaded from.
+
+pub mod deflate;
+pub mod exr;
+pub mod hdr;
+pub mod pfm;
+pub mod png;
+pub mod ppm;
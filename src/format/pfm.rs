@@ -0,0 +1,87 @@
+use std::io::{self, Read, Write};
+
+use crate::color::Color;
+
+/// Writes linear RGB pixels, given top row first, as a little-endian color
+/// Portable Float Map. PFM stores its rows bottom to top.
+pub fn write_pfm<W: Write>(out: &mut W, width: usize, height: usize, pixels: &[Color]) -> io::Result<()> {
+  // a negative scale marks the data as little-endian
+  out.write_fmt(format_args!("PF\n{} {}\n-1.0\n", width, height))?;
+
+  let mut row = Vec::with_capacity(width * 12);
+  for y in (0..height).rev() {
+    row.clear();
+    for pixel in &pixels[y * width..(y + 1) * width] {
+      row.extend_from_slice(&pixel.r.to_le_bytes());
+      row.extend_from_slice(&pixel.g.to_le_bytes());
+      row.extend_from_slice(&pixel.b.to_le_bytes());
+    }
+    out.write_all(&row)?;
+  }
+
+  Ok(())
+}
+
+/// Reads a color (`PF`) or greyscale (`Pf`) Portable Float Map of either
+/// byte order, returning its width, height and pixels top row first.
+pub fn read_pfm<R: Read>(input: &mut R) -> io::Result<(usize, usize, Vec<Color>)> {
+  let mut data = Vec::new();
+  input.read_to_end(&mut data)?;
+
+  // the magic number, size and scale are separated by single whitespace
+  // characters, the last one right before the binary data
+  let mut fields = Vec::new();
+  let mut position = 0;
+  while fields.len() < 4 {
+    let start = position;
+    while position < data.len() && !data[position].is_ascii_whitespace() {
+      position += 1;
+    }
+    if position >= data.len() {
+      return Err(invalid("the header is truncated"));
+    }
+    if position > start {
+      fields.push(String::from_utf8_lossy(&data[start..position]).into_owned());
+    }
+    position += 1;
+  }
+
+  let channels = match fields[0].as_str() {
+    "PF" => 3,
+    "Pf" => 1,
+    _ => return Err(invalid("not a Portable Float Map")),
+  };
+  let (width, height, scale) = match (fields[1].parse::<usize>(), fields[2].parse::<usize>(), fields[3].parse::<f32>()) {
+    (Ok(width), Ok(height), Ok(scale)) if scale != 0.0 => (width, height, scale),
+    _ => return Err(invalid("bad size or scale")),
+  };
+
+  // check the size against the data before allocating for it
+  let (row_bytes, total_bytes) = width.checked_mul(channels * 4)
+    .and_then(|row_bytes| Some((row_bytes, row_bytes.checked_mul(height)?)))
+    .ok_or_else(|| invalid("the size is too large"))?;
+  if total_bytes > data.len() - position {
+    return Err(invalid("the pixel data is truncated"));
+  }
+  let samples = &data[position..position + total_bytes];
+  let sample = |bytes: &[u8]| {
+    let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+    if scale < 0.0 { f32::from_le_bytes(bytes) } else { f32::from_be_bytes(bytes) }
+  };
+
+  let mut pixels = Vec::with_capacity(width * height);
+  for row in samples.chunks(row_bytes.max(1)).rev() {
+    for pixel in row.chunks(channels * 4) {
+      pixels.push(match channels {
+        3 => Color::new(sample(&pixel[0..]), sample(&pixel[4..]), sample(&pixel[8..])),
+        _ => Color::gray(sample(pixel)),
+      });
+    }
+  }
+
+  Ok((width, height, pixels))
+}
+
+fn invalid(message: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
@@ -0,0 +1,184 @@
+//! A dependency-free PNG encoder for truecolor images.
+
+use std::io::{self, Write};
+
+use crate::format::deflate::{zlib_compress, Compression};
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorType {
+  Rgb,
+  Rgba,
+}
+
+impl ColorType {
+  pub fn channels(self) -> usize {
+    match self {
+      ColorType::Rgb => 3,
+      ColorType::Rgba => 4,
+    }
+  }
+
+  fn code(self) -> u8 {
+    match self {
+      ColorType::Rgb => 2,
+      ColorType::Rgba => 6,
+    }
+  }
+}
+
+/// Interleaved channel samples, row by row from the top left corner.
+#[derive(Debug, Clone, Copy)]
+pub enum Samples<'a> {
+  Eight(&'a [u8]),
+  Sixteen(&'a [u16]),
+}
+
+impl Samples<'_> {
+  fn len(&self) -> usize {
+    match self {
+      Samples::Eight(samples) => samples.len(),
+      Samples::Sixteen(samples) => samples.len(),
+    }
+  }
+
+  fn bit_depth(&self) -> u8 {
+    match self {
+      Samples::Eight(_) => 8,
+      Samples::Sixteen(_) => 16,
+    }
+  }
+
+  /// The samples as bytes, with 16-bit values in network byte order.
+  fn to_bytes(self) -> Vec<u8> {
+    match self {
+      Samples::Eight(samples) => samples.to_vec(),
+      Samples::Sixteen(samples) => samples.iter().flat_map(|s| s.to_be_bytes()).collect(),
+    }
+  }
+}
+
+/// Encodes an image as PNG.
+pub fn write_png<W: Write>(
+  out: &mut W,
+  width: usize,
+  height: usize,
+  color_type: ColorType,
+  samples: Samples,
+  compression: Compression,
+) -> io::Result<()> {
+  if width == 0 || height == 0 || width > u32::MAX as usize || height > u32::MAX as usize {
+    return Err(io::Error::new(io::ErrorKind::InvalidInput, "PNG dimensions must be between 1 and 2^32 - 1"));
+  }
+  if samples.len() != width * height * color_type.channels() {
+    return Err(io::Error::new(io::ErrorKind::InvalidInput, "sample count does not match the PNG dimensions"));
+  }
+
+  let bit_depth = samples.bit_depth();
+  let bytes_per_pixel = color_type.channels() * bit_depth as usize / 8;
+  let filtered = filter(&samples.to_bytes(), width * bytes_per_pixel, bytes_per_pixel);
+
+  let mut header = Vec::with_capacity(13);
+  header.extend_from_slice(&(width as u32).to_be_bytes());
+  header.extend_from_slice(&(height as u32).to_be_bytes());
+  // no compression, filter or interlace method choices exist beyond zero
+  header.extend_from_slice(&[bit_depth, color_type.code(), 0, 0, 0]);
+
+  out.write_all(&SIGNATURE)?;
+  write_chunk(out, b"IHDR", &header)?;
+  write_chunk(out, b"IDAT", &zlib_compress(&filtered, compression))?;
+  write_chunk(out, b"IEND", &[])
+}
+
+fn write_chunk<W: Write>(out: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+  out.write_all(&(data.len() as u32).to_be_bytes())?;
+  out.write_all(kind)?;
+  out.write_all(data)?;
+
+  let crc = crc32_update(crc32_update(!0, kind), data);
+  out.write_all(&(!crc).to_be_bytes())
+}
+
+/// Prefixes every row with the filter type that makes it smallest, judged by
+/// the usual minimum sum of absolute differences heuristic.
+fn filter(data: &[u8], stride: usize, bytes_per_pixel: usize) -> Vec<u8> {
+  let rows = data.len() / stride;
+  let mut out = Vec::with_capacity(rows * (stride + 1));
+  let zero_row = vec![0; stride];
+  let mut candidate = vec![0; stride];
+  let mut best = vec![0; stride];
+
+  for y in 0..rows {
+    let row = &data[y * stride..(y + 1) * stride];
+    let above = if y == 0 { &zero_row[..] } else { &data[(y - 1) * stride..y * stride] };
+
+    let mut best_filter = 0;
+    let mut best_score = u64::MAX;
+    for kind in 0..5u8 {
+      for i in 0..stride {
+        let left = if i >= bytes_per_pixel { row[i - bytes_per_pixel] } else { 0 };
+        let upper_left = if i >= bytes_per_pixel { above[i - bytes_per_pixel] } else { 0 };
+        let predictor = match kind {
+          0 => 0,
+          1 => left,
+          2 => above[i],
+          3 => ((left as u16 + above[i] as u16) / 2) as u8,
+          _ => paeth(left, above[i], upper_left),
+        };
+        candidate[i] = row[i].wrapping_sub(predictor);
+      }
+
+      let score: u64 = candidate.iter().map(|&b| (b as i8).unsigned_abs() as u64).sum();
+      if score < best_score {
+        best_score = score;
+        best_filter = kind;
+        best.copy_from_slice(&candidate);
+      }
+    }
+
+    out.push(best_filter);
+    out.extend_from_slice(&best);
+  }
+
+  out
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+  let p = a as i16 + b as i16 - c as i16;
+  let pa = (p - a as i16).abs();
+  let pb = (p - b as i16).abs();
+  let pc = (p - c as i16).abs();
+
+  if pa <= pb && pa <= pc { a } else if pb <= pc { b } else { c }
+}
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+  let mut table = [0u32; 256];
+  let mut n = 0;
+  while n < 256 {
+    let mut c = n as u32;
+    let mut k = 0;
+    while k < 8 {
+      c = if c & 1 != 0 { 0xedb88320 ^ (c >> 1) } else { c >> 1 };
+      k += 1;
+    }
+    table[n] = c;
+    n += 1;
+  }
+  table
+}
+
+fn crc32_update(mut crc: u32, data: &[u8]) -> u32 {
+  for &byte in data {
+    crc = CRC_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8);
+  }
+  crc
+}
+
+/// The CRC-32 used by PNG chunks (and zip, gzip and Ethernet).
+pub fn crc32(data: &[u8]) -> u32 {
+  !crc32_update(!0, data)
+}
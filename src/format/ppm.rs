@@ -0,0 +1,7 @@
+use std::io::{self, Write};
+
+/// Writes 8-bit RGB samples as a binary (P6) PPM.
+pub fn write_ppm<W: Write>(out: &mut W, width: usize, height: usize, rgb: &[u8]) -> io::Result<()> {
+  out.write_fmt(format_args!("P6 {} {} 255\n", width, height))?;
+  out.write_all(rgb)
+}
@@ -0,0 +1,15 @@
+use crate::vector::Vector3D;
+
+/// The nearest intersection of a ray with the scene.
+#[derive(Debug, Clone, Copy)]
+pub struct Hit {
+  /// Distance along the ray to the hit point.
+  pub distance: f32,
+  pub point: Vector3D,
+  /// Unit surface normal at `point`, facing the side the ray came from.
+  pub normal: Vector3D,
+  /// Surface texture coordinates, for objects that have them.
+  pub uv: Option<(f32, f32)>,
+  /// Index of the object that was hit.
+  pub object: usize,
+}
@@ -0,0 +1,200 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use crate::color::Color;
+use crate::format::deflate::Compression;
+use crate::format::exr::{self, Channel, PixelType};
+use crate::format::png::{self, ColorType, Samples};
+use crate::format::{hdr, pfm, ppm};
+use crate::tonemap::DisplayTransform;
+
+/// A floating point RGB image holding linear radiance, stored row by row
+/// from the top left corner.
+#[derive(Debug, Clone)]
+pub struct Image {
+  pub width: usize,
+  pub height: usize,
+  pub pixels: Vec<Color>,
+  /// Extra passes such as depth or normals, only kept by EXR output.
+  pub layers: Vec<Layer>,
+}
+
+/// A named set of channels covering the whole image, such as the depth or
+/// normal pass.
+#[derive(Debug, Clone)]
+pub struct Layer {
+  pub name: String,
+  pub channels: Vec<String>,
+  /// How the channels are stored in EXR files. Passes whose exact values
+  /// matter, like depth or ids, should use `PixelType::Float`.
+  pub pixel_type: PixelType,
+  /// Every channel of a pixel in turn, row by row from the top left corner.
+  pub samples: Vec<f32>,
+}
+
+impl Layer {
+  pub fn new(name: &str, channels: &[&str], pixel_type: PixelType, width: usize, height: usize) -> Layer {
+    Layer {
+      name: name.to_string(),
+      channels: channels.iter().map(|c| c.to_string()).collect(),
+      pixel_type,
+      samples: vec![0.0; width * height * channels.len()],
+    }
+  }
+
+  /// The channels of pixel `index`, counted row by row.
+  pub fn pixel(&self, index: usize) -> &[f32] {
+    let n = self.channels.len();
+    &self.samples[index * n..(index + 1) * n]
+  }
+
+  pub fn set_pixel(&mut self, index: usize, values: &[f32]) {
+    let n = self.channels.len();
+    self.samples[index * n..(index + 1) * n].copy_from_slice(values);
+  }
+
+  /// Every sample of channel `c`, one per pixel.
+  pub fn channel(&self, c: usize) -> Vec<f32> {
+    self.samples.iter().skip(c).step_by(self.channels.len()).copied().collect()
+  }
+}
+
+/// How `Image::save_with_options` encodes each format.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SaveOptions {
+  /// Maps radiance to the 8-bit values of PNG and PPM files.
+  pub display: DisplayTransform,
+  pub exr_compression: exr::Compression,
+  /// Precision of the beauty pass in EXR files. Layers pick their own.
+  pub exr_beauty: PixelType,
+}
+
+impl Default for SaveOptions {
+  fn default() -> SaveOptions {
+    SaveOptions {
+      display: DisplayTransform::default(),
+      exr_compression: exr::Compression::Zip,
+      exr_beauty: PixelType::Half,
+    }
+  }
+}
+
+impl Image {
+  /// File extensions `save` knows how to write.
+  pub const EXTENSIONS: &'static [&'static str] = &["exr", "hdr", "pfm", "png", "ppm"];
+
+  pub fn new(width: usize, height: usize) -> Image {
+    Image { width, height, pixels: vec![Color::BLACK; width * height], layers: Vec::new() }
+  }
+
+  pub fn layer(&self, name: &str) -> Option<&Layer> {
+    self.layers.iter().find(|layer| layer.name == name)
+  }
+
+  pub fn pixel(&self, x: usize, y: usize) -> Color {
+    self.pixels[y * self.width + x]
+  }
+
+  pub fn set_pixel(&mut self, x: usize, y: usize, color: Color) {
+    self.pixels[y * self.width + x] = color;
+  }
+
+  /// Quantizes the image to 8-bit sRGB for display formats.
+  pub fn to_rgb8(&self, display: &DisplayTransform) -> Vec<u8> {
+    display.to_rgb8(self)
+  }
+
+  /// Writes the image as a binary (P6) PPM.
+  pub fn write_ppm<W: Write>(&self, out: &mut W, display: &DisplayTransform) -> io::Result<()> {
+    ppm::write_ppm(out, self.width, self.height, &self.to_rgb8(display))
+  }
+
+  /// Writes the image as an 8-bit RGB PNG.
+  pub fn write_png<W: Write>(&self, out: &mut W, display: &DisplayTransform) -> io::Result<()> {
+    let rgb = self.to_rgb8(display);
+    png::write_png(out, self.width, self.height, ColorType::Rgb, Samples::Eight(&rgb), Compression::Fixed)
+  }
+
+  /// Writes the unclamped image as a Portable Float Map.
+  pub fn write_pfm<W: Write>(&self, out: &mut W) -> io::Result<()> {
+    pfm::write_pfm(out, self.width, self.height, &self.pixels)
+  }
+
+  /// Writes the unclamped image as a Radiance RGBE picture.
+  pub fn write_hdr<W: Write>(&self, out: &mut W) -> io::Result<()> {
+    hdr::write_hdr(out, self.width, self.height, &self.pixels)
+  }
+
+  /// Writes the image and all of its layers as a multi-channel OpenEXR
+  /// file. The beauty pass goes in the `R`, `G` and `B` channels and each
+  /// layer channel in `layer.channel`, or just `layer` for unnamed ones.
+  pub fn write_exr<W: Write>(&self, out: &mut W, compression: exr::Compression, beauty: PixelType) -> io::Result<()> {
+    let mut planes = Vec::new();
+    for c in 0..3 {
+      planes.push((["R", "G", "B"][c].to_string(), beauty, self.pixels.iter().map(|p| p[c]).collect::<Vec<f32>>()));
+    }
+    for layer in &self.layers {
+      for (c, channel) in layer.channels.iter().enumerate() {
+        let name = if channel.is_empty() { layer.name.clone() } else { format!("{}.{}", layer.name, channel) };
+        planes.push((name, layer.pixel_type, layer.channel(c)));
+      }
+    }
+
+    let channels: Vec<Channel> = planes
+      .iter()
+      .map(|(name, pixel_type, samples)| Channel { name: name.clone(), pixel_type: *pixel_type, samples })
+      .collect();
+    exr::write_exr(out, self.width, self.height, &channels, compression)
+  }
+
+  /// Saves the image in the format implied by the extension of `path`.
+  pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+    self.save_with_options(path, &SaveOptions::default())
+  }
+
+  /// Saves the image in the format implied by the extension of `path`,
+  /// encoded as described by `options`.
+  pub fn save_with_options<P: AsRef<Path>>(&self, path: P, options: &SaveOptions) -> io::Result<()> {
+    let path = path.as_ref();
+    let extension = path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase());
+    let display = &options.display;
+
+    match extension.as_deref() {
+      Some("exr") => self.save_with(path, |image, out| image.write_exr(out, options.exr_compression, options.exr_beauty)),
+      Some("hdr") => self.save_with(path, Image::write_hdr),
+      Some("pfm") => self.save_with(path, Image::write_pfm),
+      Some("png") => self.save_with(path, |image, out| image.write_png(out, display)),
+      Some("ppm") => self.save_with(path, |image, out| image.write_ppm(out, display)),
+      _ => Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("unsupported output format, expected one of: {}", Image::EXTENSIONS.join(", ")),
+      )),
+    }
+  }
+
+  /// Loads a Radiance `.hdr` or `.pfm` image, as used for environment maps.
+  pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Image> {
+    let path = path.as_ref();
+    let extension = path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase());
+    let read = match extension.as_deref() {
+      Some("hdr") => hdr::read_hdr,
+      Some("pfm") => pfm::read_pfm,
+      _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, "unsupported input format, expected one of: hdr, pfm")),
+    };
+
+    let (width, height, pixels) = read(&mut BufReader::new(File::open(path)?))?;
+    Ok(Image { width, height, pixels, layers: Vec::new() })
+  }
+
+  pub fn save_ppm<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+    self.save_with(path.as_ref(), |image, out| image.write_ppm(out, &DisplayTransform::default()))
+  }
+
+  fn save_with<F>(&self, path: &Path, write: F) -> io::Result<()>
+  where F: Fn(&Image, &mut BufWriter<File>) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    write(self, &mut file)?;
+    file.flush()
+  }
+}
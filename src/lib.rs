@@ -0,0 +1,52 @@
+//! A very naive ray tracer.
+
+pub mod axis_aligned_box;
+pub mod bounding_box;
+pub mod bvh;
+pub mod camera;
+pub mod color;
+pub mod disc;
+pub mod environment;
+pub mod filter;
+pub mod format;
+pub mod hit;
+pub mod image;
+pub mod light;
+pub mod material;
+pub mod plane;
+pub mod random;
+pub mod ray;
+pub mod renderer;
+pub mod sampling;
+pub mod scene;
+pub mod scene_file;
+pub mod shape;
+pub mod sky;
+pub mod sphere;
+pub mod tonemap;
+pub mod triangle;
+pub mod vector;
+
+pub use crate::axis_aligned_box::AxisAlignedBox;
+pub use crate::bounding_box::BoundingBox;
+pub use crate::bvh::{Bvh, BvhStats};
+pub use crate::camera::{Camera, Projection};
+pub use crate::color::Color;
+pub use crate::disc::Disc;
+pub use crate::environment::EnvironmentLight;
+pub use crate::filter::Filter;
+pub use crate::hit::Hit;
+pub use crate::image::{Image, Layer, SaveOptions};
+pub use crate::light::{DirectionalLight, DiscLight, Light, LightSample, PointLight, RectangleLight, SphereLight, SpotLight};
+pub use crate::material::Material;
+pub use crate::plane::Plane;
+pub use crate::ray::Ray;
+pub use crate::renderer::{render, RenderSettings, Renderer};
+pub use crate::scene::{Object, Scene};
+pub use crate::scene_file::{LoadError, ParseError, SceneDescription};
+pub use crate::shape::Shape;
+pub use crate::sky::{Sky, SkyLight};
+pub use crate::sphere::Sphere;
+pub use crate::tonemap::{Dither, DisplayTransform, ToneMapper};
+pub use crate::triangle::{Triangle, TriangleMesh};
+pub use crate::vector::Vector3D;
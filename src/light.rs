@@ -0,0 +1,312 @@
+use std::f32::consts::{PI, TAU};
+use std::fmt::Debug;
+
+use crate::color::Color;
+use crate::disc::Disc;
+use crate::ray::Ray;
+use crate::sampling::{concentric_disc, orthonormal_basis, uniform_cone};
+use crate::sphere::Sphere;
+use crate::vector::Vector3D;
+
+/// The light a single light source delivers to a point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LightSample {
+  /// Unit vector from the point towards the light.
+  pub direction: Vector3D,
+  /// How far away the light is, infinite for directional lights. Shadow rays
+  /// stop here.
+  pub distance: f32,
+  /// RGB irradiance on a surface facing the light head on.
+  pub irradiance: Color,
+}
+
+/// Something that lights the scene. Implement this to add new kinds of
+/// lights without touching the renderer.
+pub trait Light: Debug + Send + Sync {
+  /// The light reaching `point` from a spot on the light picked by the
+  /// uniform random numbers `u`, or `None` if it can't reach the point at
+  /// all. Averaging many samples gives the light's total irradiance.
+  fn sample(&self, point: Vector3D, u: (f32, f32)) -> Option<LightSample>;
+
+  /// How many samples to average at every shaded point. Lights that arrive
+  /// from a single direction only need the one.
+  fn samples(&self) -> usize {
+    1
+  }
+
+  /// Where `ray` first hits the light itself and the light seen there, for
+  /// lights with a surface.
+  fn emission(&self, _ray: &Ray) -> Option<(f32, Color)> {
+    None
+  }
+
+  /// The light seen looking along `direction` by rays that escape the
+  /// scene, for lights infinitely far away.
+  fn environment(&self, _direction: Vector3D) -> Color {
+    Color::BLACK
+  }
+}
+
+/// A light infinitely far away, shining along `direction`. With an
+/// `angle` it's a disc in the sky like the sun, visible to rays that escape
+/// the scene and casting shadows that soften with distance.
+#[derive(Debug, Clone)]
+pub struct DirectionalLight {
+  pub direction: Vector3D,
+  pub color: Color,
+  /// Irradiance on a surface facing the light.
+  pub intensity: f32,
+  /// Angular diameter of the disc in degrees, or zero for a point in the
+  /// sky with hard shadows.
+  pub angle: f32,
+  /// Number of shadow rays spread over the disc at every shaded point.
+  pub samples: usize,
+}
+
+impl DirectionalLight {
+  /// `1 - cos` of the disc's angular radius.
+  fn one_minus_cos_radius(&self) -> f32 {
+    let sin_half = (self.angle.to_radians() / 4.0).sin();
+    2.0 * sin_half * sin_half
+  }
+}
+
+impl Light for DirectionalLight {
+  fn sample(&self, _point: Vector3D, u: (f32, f32)) -> Option<LightSample> {
+    let axis = -self.direction.normalize();
+    let direction = if self.angle > 0.0 { uniform_cone(axis, self.one_minus_cos_radius(), u) } else { axis };
+    Some(LightSample { direction, distance: f32::INFINITY, irradiance: self.color * self.intensity })
+  }
+
+  fn samples(&self) -> usize {
+    if self.angle > 0.0 { self.samples } else { 1 }
+  }
+
+  fn environment(&self, direction: Vector3D) -> Color {
+    let one_minus_cos_radius = self.one_minus_cos_radius();
+    if self.angle <= 0.0 || 1.0 - direction.normalize().dot(&-self.direction.normalize()) > one_minus_cos_radius {
+      return Color::BLACK;
+    }
+    // the disc's radiance spreads the irradiance over its solid angle
+    self.color * (self.intensity * PI / (TAU * one_minus_cos_radius))
+  }
+}
+
+/// A light shining equally in all directions from `position`, falling off
+/// with the square of the distance.
+#[derive(Debug, Clone)]
+pub struct PointLight {
+  pub position: Vector3D,
+  pub color: Color,
+  /// Irradiance one unit away from the light.
+  pub intensity: f32,
+}
+
+impl Light for PointLight {
+  fn sample(&self, point: Vector3D, _u: (f32, f32)) -> Option<LightSample> {
+    let offset = self.position - point;
+    let distance_squared = offset.dot(&offset);
+    if distance_squared == 0.0 { return None; }
+
+    let distance = distance_squared.sqrt();
+    Some(LightSample {
+      direction: offset * (1.0 / distance),
+      distance,
+      irradiance: self.color * (self.intensity / distance_squared),
+    })
+  }
+}
+
+/// A point light limited to a cone around `direction`. It's at full
+/// strength within `inner_angle` of the axis and fades out smoothly towards
+/// `outer_angle`, both in degrees.
+#[derive(Debug, Clone)]
+pub struct SpotLight {
+  pub position: Vector3D,
+  pub direction: Vector3D,
+  pub color: Color,
+  /// Irradiance one unit away along the axis.
+  pub intensity: f32,
+  pub inner_angle: f32,
+  pub outer_angle: f32,
+}
+
+impl SpotLight {
+  /// How much of the light leaves in `direction`, from 0 outside the cone to
+  /// 1 inside the inner one.
+  pub fn falloff(&self, direction: Vector3D) -> f32 {
+    let cos_angle = direction.normalize().dot(&self.direction.normalize());
+    let cos_outer = self.outer_angle.to_radians().cos();
+    let cos_inner = self.inner_angle.to_radians().cos();
+    if cos_angle <= cos_outer { return 0.0; }
+    if cos_angle >= cos_inner { return 1.0; }
+
+    let t = (cos_angle - cos_outer) / (cos_inner - cos_outer);
+    t * t * (3.0 - 2.0 * t)
+  }
+}
+
+impl Light for SpotLight {
+  fn sample(&self, point: Vector3D, u: (f32, f32)) -> Option<LightSample> {
+    let point_light = PointLight { position: self.position, color: self.color, intensity: self.intensity };
+    let sample = point_light.sample(point, u)?;
+    let falloff = self.falloff(-sample.direction);
+    if falloff == 0.0 { return None; }
+
+    Some(LightSample { irradiance: sample.irradiance * falloff, ..sample })
+  }
+}
+
+/// The light delivered to `point` by `light_point`, picked uniformly over a
+/// one-sided emitter with the given `area` and `normal` and glowing with
+/// `radiance`.
+fn area_sample(point: Vector3D, light_point: Vector3D, normal: Vector3D, area: f32, radiance: Color) -> Option<LightSample> {
+  let offset = light_point - point;
+  let distance_squared = offset.dot(&offset);
+  if distance_squared == 0.0 { return None; }
+
+  let distance = distance_squared.sqrt();
+  let direction = offset * (1.0 / distance);
+  // the back of the light doesn't shine
+  let cos_light = -direction.dot(&normal);
+  if cos_light <= 0.0 { return None; }
+
+  Some(LightSample { direction, distance, irradiance: radiance * (cos_light * area / distance_squared) })
+}
+
+/// The light seen looking at the side of a one-sided emitter with `normal`
+/// that `ray` hits.
+fn emission_towards(ray: &Ray, normal: Vector3D, color: Color) -> Color {
+  if ray.direction.dot(&normal) < 0.0 { color } else { Color::BLACK }
+}
+
+/// A rectangle centered on `position` with sides `edge_u` and `edge_v`,
+/// shining from the side that `edge_u × edge_v` points to.
+///
+/// Like the other area lights, `intensity` is how bright it looks and also
+/// the irradiance it delivers to a surface right up against it. Shading
+/// points average `samples` shadow rays to points spread over it.
+#[derive(Debug, Clone)]
+pub struct RectangleLight {
+  pub position: Vector3D,
+  pub edge_u: Vector3D,
+  pub edge_v: Vector3D,
+  pub color: Color,
+  pub intensity: f32,
+  pub samples: usize,
+}
+
+impl RectangleLight {
+  pub fn normal(&self) -> Vector3D {
+    self.edge_u.cross(&self.edge_v).normalize()
+  }
+
+  pub fn area(&self) -> f32 {
+    self.edge_u.cross(&self.edge_v).magnitude()
+  }
+}
+
+impl Light for RectangleLight {
+  fn sample(&self, point: Vector3D, (s, t): (f32, f32)) -> Option<LightSample> {
+    let light_point = self.position + self.edge_u * (s - 0.5) + self.edge_v * (t - 0.5);
+    area_sample(point, light_point, self.normal(), self.area(), self.color * (self.intensity / PI))
+  }
+
+  fn samples(&self) -> usize {
+    self.samples
+  }
+
+  fn emission(&self, ray: &Ray) -> Option<(f32, Color)> {
+    let normal = self.normal();
+    let distance = (self.position - ray.origin).dot(&normal) / ray.direction.dot(&normal);
+    if !(distance > 0.0 && distance < f32::INFINITY) { return None; }
+
+    // position within the rectangle along each edge, from -0.5 to 0.5
+    let offset = ray.at(distance) - self.position;
+    let s = offset.dot(&self.edge_u) / self.edge_u.dot(&self.edge_u);
+    let t = offset.dot(&self.edge_v) / self.edge_v.dot(&self.edge_v);
+    if s.abs() > 0.5 || t.abs() > 0.5 { return None; }
+
+    Some((distance, emission_towards(ray, normal, self.color * self.intensity)))
+  }
+}
+
+/// A one-sided round light shining towards `normal`.
+#[derive(Debug, Clone)]
+pub struct DiscLight {
+  pub center: Vector3D,
+  pub normal: Vector3D,
+  pub radius: f32,
+  pub color: Color,
+  pub intensity: f32,
+  pub samples: usize,
+}
+
+impl Light for DiscLight {
+  fn sample(&self, point: Vector3D, (s, t): (f32, f32)) -> Option<LightSample> {
+    let normal = self.normal.normalize();
+    let (tangent, bitangent) = orthonormal_basis(normal);
+    let (x, y) = concentric_disc(s, t);
+    let light_point = self.center + (tangent * x + bitangent * y) * self.radius;
+    let area = PI * self.radius * self.radius;
+    area_sample(point, light_point, normal, area, self.color * (self.intensity / PI))
+  }
+
+  fn samples(&self) -> usize {
+    self.samples
+  }
+
+  fn emission(&self, ray: &Ray) -> Option<(f32, Color)> {
+    let disc = Disc { center: self.center, normal: self.normal, radius: self.radius };
+    let t = disc.intersects(ray, 0.0, f32::INFINITY)?;
+    Some((t, emission_towards(ray, self.normal, self.color * self.intensity)))
+  }
+}
+
+/// A glowing ball, shining in every direction. Seen from far away it
+/// delivers `intensity * (radius / distance)²`.
+#[derive(Debug, Clone)]
+pub struct SphereLight {
+  pub position: Vector3D,
+  pub radius: f32,
+  pub color: Color,
+  pub intensity: f32,
+  pub samples: usize,
+}
+
+impl Light for SphereLight {
+  /// Samples directions uniformly within the cone the sphere fills as seen
+  /// from `point`, so none are wasted on its hidden far side.
+  fn sample(&self, point: Vector3D, (s, t): (f32, f32)) -> Option<LightSample> {
+    let offset = self.position - point;
+    let distance_squared = offset.dot(&offset);
+    let radius_squared = self.radius * self.radius;
+    if distance_squared <= radius_squared { return None; }
+
+    let axis = offset * (1.0 / distance_squared.sqrt());
+    let sin_max_squared = radius_squared / distance_squared;
+    let cos_max = (1.0 - sin_max_squared).sqrt();
+    // 1 - cos_max without cancelling out for small, far away lights
+    let one_minus_cos_max = sin_max_squared / (1.0 + cos_max);
+
+    let direction = uniform_cone(axis, one_minus_cos_max, (s, t));
+
+    // rays along the edge of the cone may just miss through rounding
+    let sphere = Sphere { position: self.position, radius: self.radius };
+    let distance = sphere.intersects(&Ray::new(point, direction), 0.0, f32::INFINITY)
+      .unwrap_or_else(|| (distance_squared - radius_squared).sqrt());
+
+    let solid_angle = TAU * one_minus_cos_max;
+    Some(LightSample { direction, distance, irradiance: self.color * (self.intensity / PI * solid_angle) })
+  }
+
+  fn samples(&self) -> usize {
+    self.samples
+  }
+
+  fn emission(&self, ray: &Ray) -> Option<(f32, Color)> {
+    let sphere = Sphere { position: self.position, radius: self.radius };
+    let t = sphere.intersects(ray, 0.0, f32::INFINITY)?;
+    Some((t, self.color * self.intensity))
+  }
+}
@@ -0,0 +1,34 @@
+use crate::color::Color;
+use crate::vector::Vector3D;
+
+/// How a surface reflects light: a diffuse color plus Blinn-Phong
+/// highlights in the `specular` color, which get smaller and sharper as
+/// `shininess` goes up.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Material {
+  pub diffuse: Color,
+  pub specular: Color,
+  pub shininess: f32,
+}
+
+impl Material {
+  /// The fraction of the light arriving from `to_light` that's reflected
+  /// towards `to_viewer`, for a surface facing `normal`. Multiplied by the
+  /// light's irradiance this gives the color seen.
+  pub fn reflectance(&self, normal: Vector3D, to_viewer: Vector3D, to_light: Vector3D) -> Color {
+    if self.specular == Color::BLACK { return self.diffuse; }
+
+    // highlights peak where the normal lies halfway between the light and
+    // the viewer
+    let halfway = (to_viewer.normalize() + to_light.normalize()).normalize();
+    let cos_halfway = normal.dot(&halfway).max(0.0);
+    self.diffuse + self.specular * cos_halfway.powf(self.shininess)
+  }
+}
+
+/// A plain diffuse surface.
+impl From<Color> for Material {
+  fn from(diffuse: Color) -> Material {
+    Material { diffuse, specular: Color::BLACK, shininess: 32.0 }
+  }
+}
@@ -0,0 +1,43 @@
+use crate::bounding_box::BoundingBox;
+use crate::ray::Ray;
+use crate::shape::Shape;
+use crate::vector::Vector3D;
+
+/// Smallest cosine between a ray and a flat surface that still counts as a
+/// hit; anything closer to parallel is treated as a miss.
+pub(crate) const PARALLEL_EPSILON: f32 = 1e-8;
+
+/// An infinite plane through `point`, facing `normal`.
+#[derive(Debug, Clone)]
+pub struct Plane {
+  pub point: Vector3D,
+  pub normal: Vector3D,
+}
+
+impl Plane {
+  pub fn intersects(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<f32> {
+    let denom = self.normal.dot(&ray.direction);
+    if denom.abs() < PARALLEL_EPSILON { return None; }
+
+    let t = (self.point - ray.origin).dot(&self.normal) / denom;
+    if t > t_min && t < t_max { Some(t) } else { None }
+  }
+
+  pub fn surface_normal(&self, _hit_point: &Vector3D) -> Vector3D {
+    self.normal.normalize()
+  }
+}
+
+impl Shape for Plane {
+  fn intersect(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<f32> {
+    self.intersects(ray, t_min, t_max)
+  }
+
+  fn normal(&self, point: &Vector3D) -> Vector3D {
+    self.surface_normal(point)
+  }
+
+  fn bounding_box(&self) -> BoundingBox {
+    BoundingBox::infinite()
+  }
+}
@@ -0,0 +1,36 @@
+/// A small, fast PCG32 generator. Renders seed one per pixel from its
+/// coordinates so the output never depends on which thread drew the samples.
+#[derive(Debug, Clone)]
+pub struct Rng {
+  state: u64,
+}
+
+const MULTIPLIER: u64 = 6364136223846793005;
+const INCREMENT: u64 = 1442695040888963407;
+
+impl Rng {
+  pub fn new(seed: u64) -> Rng {
+    let mut rng = Rng { state: 0 };
+    rng.next_u32();
+    rng.state = rng.state.wrapping_add(seed);
+    rng.next_u32();
+    rng
+  }
+
+  /// A generator unique to the pixel at `(x, y)`.
+  pub fn for_pixel(x: usize, y: usize) -> Rng {
+    Rng::new(((y as u64) << 32 | x as u64).wrapping_mul(0x9e3779b97f4a7c15))
+  }
+
+  pub fn next_u32(&mut self) -> u32 {
+    let old = self.state;
+    self.state = old.wrapping_mul(MULTIPLIER).wrapping_add(INCREMENT);
+    let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+    xorshifted.rotate_right((old >> 59) as u32)
+  }
+
+  /// A uniformly distributed number in `[0, 1)`.
+  pub fn next_f32(&mut self) -> f32 {
+    (self.next_u32() >> 8) as f32 * (1.0 / (1u32 << 24) as f32)
+  }
+}
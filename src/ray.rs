@@ -0,0 +1,32 @@
+use crate::vector::Vector3D;
+
+/// Base distance secondary rays are pushed off a surface to avoid hitting it
+/// again due to floating point error. It is scaled by the magnitude of the
+/// coordinates involved, see `Ray::spawn`.
+pub const EPSILON: f32 = 1e-4;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Ray {
+  pub origin: Vector3D,
+  pub direction: Vector3D,
+}
+
+impl Ray {
+  pub fn new(origin: Vector3D, direction: Vector3D) -> Ray {
+    Ray { origin, direction }
+  }
+
+  /// Starts a ray at a surface `point` with the given `normal`, nudging the
+  /// origin off the surface towards the side `direction` leaves from.
+  pub fn spawn(point: Vector3D, normal: Vector3D, direction: Vector3D) -> Ray {
+    let scale = 1.0 + point.x.abs().max(point.y.abs()).max(point.z.abs());
+    let offset = if normal.dot(&direction) < 0.0 { -EPSILON } else { EPSILON };
+
+    Ray::new(point + normal * (offset * scale), direction)
+  }
+
+  /// The point at distance `t` along the ray.
+  pub fn at(&self, t: f32) -> Vector3D {
+    self.origin + self.direction * t
+  }
+}
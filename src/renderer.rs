@@ -0,0 +1,395 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use crate::color::Color;
+use crate::filter::Filter;
+use crate::format::exr::PixelType;
+use crate::hit::Hit;
+use crate::image::{Image, Layer};
+use crate::random::Rng;
+use crate::ray::Ray;
+use crate::sampling;
+use crate::scene::Scene;
+use crate::vector::Vector3D;
+
+/// Largest image width or height accepted from the command line or a scene
+/// file.
+pub const MAX_DIMENSION: usize = 1 << 16;
+
+#[derive(Debug, Clone)]
+pub struct RenderSettings {
+  pub width: usize,
+  pub height: usize,
+  /// Number of rays traced through every pixel.
+  pub samples: usize,
+  /// How samples are weighted into the pixels around them.
+  pub filter: Filter,
+  /// Number of worker threads, or zero to use every available core.
+  pub threads: usize,
+  /// Width and height of the square tiles handed out to workers.
+  pub tile_size: usize,
+  /// Also render depth, normal, albedo and object id layers, taken from a
+  /// ray through the center of every pixel.
+  pub aovs: bool,
+}
+
+impl Default for RenderSettings {
+  fn default() -> RenderSettings {
+    RenderSettings {
+      width: 800,
+      height: 600,
+      samples: 1,
+      filter: Filter::Box,
+      threads: 0,
+      tile_size: 32,
+      aovs: false,
+    }
+  }
+}
+
+impl RenderSettings {
+  pub fn aspect(&self) -> f32 {
+    self.width as f32 / self.height as f32
+  }
+
+  /// The number of worker threads to actually start.
+  pub fn thread_count(&self) -> usize {
+    match self.threads {
+      0 => thread::available_parallelism().map_or(1, |n| n.get()),
+      n => n,
+    }
+  }
+}
+
+/// A rectangular block of pixels rendered by a single worker.
+#[derive(Debug, Clone, Copy)]
+struct Tile {
+  x: usize,
+  y: usize,
+  width: usize,
+  height: usize,
+}
+
+/// The arbitrary output values of one pixel: the first surface seen through
+/// its center.
+#[derive(Debug, Clone, Copy)]
+struct Aov {
+  depth: f32,
+  normal: Vector3D,
+  albedo: Color,
+  /// Index of the object, or -1 where the ray escaped.
+  object: f32,
+}
+
+impl Default for Aov {
+  fn default() -> Aov {
+    Aov { depth: f32::INFINITY, normal: Vector3D::default(), albedo: Color::BLACK, object: -1.0 }
+  }
+}
+
+/// Filtered samples summed over a tile and the pixels around it that the
+/// filter spreads its samples into.
+#[derive(Debug)]
+struct Splats {
+  x: usize,
+  y: usize,
+  width: usize,
+  height: usize,
+  /// Weighted radiance of every pixel.
+  color: Vec<Color>,
+  /// Sum of the weights of every sample in `color`.
+  weight: Vec<f32>,
+}
+
+impl Splats {
+  fn new(x: usize, y: usize, width: usize, height: usize) -> Splats {
+    Splats { x, y, width, height, color: vec![Color::BLACK; width * height], weight: vec![0.0; width * height] }
+  }
+
+  /// Adds a sample at image position `(px, py)` to every pixel the filter
+  /// reaches.
+  fn add(&mut self, filter: Filter, px: f32, py: f32, color: Color) {
+    let radius = filter.radius();
+    let x_min = ((px - radius).floor().max(0.0) as usize).max(self.x);
+    let y_min = ((py - radius).floor().max(0.0) as usize).max(self.y);
+    let x_max = ((px + radius).ceil().max(0.0) as usize).min(self.x + self.width);
+    let y_max = ((py + radius).ceil().max(0.0) as usize).min(self.y + self.height);
+
+    for y in y_min..y_max {
+      let wy = filter.evaluate(py - (y as f32 + 0.5));
+      if wy == 0.0 { continue; }
+      for x in x_min..x_max {
+        let weight = wy * filter.evaluate(px - (x as f32 + 0.5));
+        if weight == 0.0 { continue; }
+        let i = (y - self.y) * self.width + x - self.x;
+        self.color[i] += color * weight;
+        self.weight[i] += weight;
+      }
+    }
+  }
+}
+
+/// A finished tile: its position in the render order, its samples and the
+/// AOVs of its pixels when enabled.
+type RenderedTile = (usize, Tile, Splats, Vec<Aov>);
+
+pub struct Renderer<'a> {
+  scene: &'a Scene,
+  settings: &'a RenderSettings,
+}
+
+impl<'a> Renderer<'a> {
+  pub fn new(scene: &'a Scene, settings: &'a RenderSettings) -> Renderer<'a> {
+    Renderer { scene, settings }
+  }
+
+  /// Renders the whole image, splitting it into tiles that worker threads
+  /// pick up as they become free. Every sample only depends on the
+  /// coordinates of its pixel, and tiles are merged in order, so the result
+  /// is the same for any number of threads.
+  pub fn render(&self) -> Image {
+    let tiles = self.tiles();
+    let next_tile = AtomicUsize::new(0);
+    let threads = self.settings.thread_count().clamp(1, tiles.len().max(1));
+
+    // build the acceleration structure up front rather than stalling every
+    // worker on the first ray
+    self.scene.bvh();
+
+    let rendered: Vec<Vec<RenderedTile>> = thread::scope(|scope| {
+      let workers: Vec<_> = (0..threads)
+        .map(|_| scope.spawn(|| {
+          let mut done = Vec::new();
+          loop {
+            let index = next_tile.fetch_add(1, Ordering::Relaxed);
+            let tile = match tiles.get(index) {
+              Some(tile) => tile,
+              None => break,
+            };
+            let aovs = if self.settings.aovs { self.render_aovs(tile) } else { Vec::new() };
+            done.push((index, *tile, self.render_tile(tile), aovs));
+            self.scene.bvh().flush_stats();
+          }
+          done
+        }))
+        .collect();
+
+      workers.into_iter().map(|worker| worker.join().expect("render thread panicked")).collect()
+    });
+
+    let (width, height) = (self.settings.width, self.settings.height);
+    let mut image = Image::new(width, height);
+    // depth and ids are stored as full floats since their exact values matter
+    let mut layers = if self.settings.aovs {
+      vec![
+        Layer::new("depth", &["Z"], PixelType::Float, width, height),
+        Layer::new("normal", &["X", "Y", "Z"], PixelType::Half, width, height),
+        Layer::new("albedo", &["R", "G", "B"], PixelType::Half, width, height),
+        Layer::new("id", &[""], PixelType::Float, width, height),
+      ]
+    } else {
+      Vec::new()
+    };
+
+    // samples near the edges of tiles land in their neighbours too, adding
+    // them up in a fixed order keeps the floating point sums identical
+    let mut rendered: Vec<RenderedTile> = rendered.into_iter().flatten().collect();
+    rendered.sort_by_key(|tile| tile.0);
+
+    let mut color = vec![Color::BLACK; width * height];
+    let mut weight = vec![0.0f32; width * height];
+    for (_, tile, splats, aovs) in rendered {
+      for y in 0..splats.height {
+        for x in 0..splats.width {
+          let (i, j) = ((splats.y + y) * width + splats.x + x, y * splats.width + x);
+          color[i] += splats.color[j];
+          weight[i] += splats.weight[j];
+        }
+      }
+
+      for (i, aov) in aovs.into_iter().enumerate() {
+        let index = (tile.y + i / tile.width) * width + tile.x + i % tile.width;
+        layers[0].set_pixel(index, &[aov.depth]);
+        layers[1].set_pixel(index, &[aov.normal.x, aov.normal.y, aov.normal.z]);
+        layers[2].set_pixel(index, &[aov.albedo.r, aov.albedo.g, aov.albedo.b]);
+        layers[3].set_pixel(index, &[aov.object]);
+      }
+    }
+
+    for (i, pixel) in image.pixels.iter_mut().enumerate() {
+      if weight[i] > 0.0 {
+        *pixel = color[i] * (1.0 / weight[i]);
+      }
+    }
+
+    image.layers = layers;
+    image
+  }
+
+  fn tiles(&self) -> Vec<Tile> {
+    let size = self.settings.tile_size.max(1);
+    let mut tiles = Vec::new();
+
+    for y in (0..self.settings.height).step_by(size) {
+      for x in (0..self.settings.width).step_by(size) {
+        tiles.push(Tile {
+          x,
+          y,
+          width: size.min(self.settings.width - x),
+          height: size.min(self.settings.height - y),
+        });
+      }
+    }
+
+    tiles
+  }
+
+  fn render_tile(&self, tile: &Tile) -> Splats {
+    let pad = self.settings.filter.radius().ceil() as usize;
+    let (x, y) = (tile.x.saturating_sub(pad), tile.y.saturating_sub(pad));
+    let width = (tile.x + tile.width + pad).min(self.settings.width) - x;
+    let height = (tile.y + tile.height + pad).min(self.settings.height) - y;
+
+    let mut splats = Splats::new(x, y, width, height);
+    for y in tile.y..tile.y + tile.height {
+      for x in tile.x..tile.x + tile.width {
+        self.render_pixel(x, y, &mut splats);
+      }
+    }
+    splats
+  }
+
+  fn render_aovs(&self, tile: &Tile) -> Vec<Aov> {
+    let mut aovs = Vec::with_capacity(tile.width * tile.height);
+    for y in tile.y..tile.y + tile.height {
+      for x in tile.x..tile.x + tile.width {
+        let u = (x as f32 + 0.5) / self.settings.width as f32;
+        let v = (y as f32 + 0.5) / self.settings.height as f32;
+        let hit = self.scene.camera.ray(u, v, self.settings.aspect()).and_then(|ray| self.scene.intersect(&ray));
+
+        aovs.push(match hit {
+          Some(hit) => Aov {
+            depth: hit.distance,
+            normal: hit.normal,
+            albedo: self.scene.object(hit.object).material.diffuse,
+            object: hit.object as f32,
+          },
+          None => Aov::default(),
+        });
+      }
+    }
+    aovs
+  }
+
+  /// Traces `samples` rays through jittered points spread evenly over the
+  /// pixel, or a single ray through its center, and random points of the
+  /// lens, and splats them into the pixels around.
+  fn render_pixel(&self, x: usize, y: usize, splats: &mut Splats) {
+    let samples = self.settings.samples.max(1);
+    let mut rng = Rng::for_pixel(x, y);
+    let camera = &self.scene.camera;
+
+    for i in 0..samples {
+      let (dx, dy) = if samples == 1 { (0.5, 0.5) } else { sampling::stratified(i, samples, &mut rng) };
+      // pinhole cameras skip the lens samples, keeping their noise the same
+      let lens = if camera.aperture <= 0.0 { (0.5, 0.5) } else { (rng.next_f32(), rng.next_f32()) };
+
+      let (px, py) = (x as f32 + dx, y as f32 + dy);
+      let u = px / self.settings.width as f32;
+      let v = py / self.settings.height as f32;
+      // fisheye cameras leave the corners outside their image circle black
+      let color = match camera.lens_ray(u, v, self.settings.aspect(), lens) {
+        Some(ray) => self.trace(&ray, &mut rng),
+        None => Color::BLACK,
+      };
+      splats.add(self.settings.filter, px, py, color);
+    }
+  }
+
+  /// Shades a single primary ray, drawing any random numbers needed from
+  /// `rng`.
+  pub fn trace(&self, ray: &Ray, rng: &mut Rng) -> Color {
+    let hit = self.scene.intersect(ray);
+    if let Some(emitted) = self.emission(ray, hit.map_or(f32::INFINITY, |hit| hit.distance)) {
+      return emitted;
+    }
+
+    match hit {
+      Some(hit) => self.shade(ray, &hit, rng),
+      None => self.background(ray),
+    }
+  }
+
+  /// The light seen along `ray` from the nearest light surface closer than
+  /// `t_max`, if any.
+  fn emission(&self, ray: &Ray, t_max: f32) -> Option<Color> {
+    let mut nearest = None;
+    let mut t_max = t_max;
+    for light in &self.scene.lights {
+      if let Some((distance, color)) = light.emission(ray) {
+        if distance < t_max {
+          t_max = distance;
+          nearest = Some(color);
+        }
+      }
+    }
+    nearest
+  }
+
+  /// The light seen by a ray that escapes the scene, black unless there are
+  /// lights such as environment maps surrounding it.
+  fn background(&self, ray: &Ray) -> Color {
+    let mut color = Color::BLACK;
+    for light in &self.scene.lights {
+      color += light.environment(ray.direction);
+    }
+    color
+  }
+
+  /// Computes the color of the surface at `hit`, seen along `ray`.
+  pub fn shade(&self, ray: &Ray, hit: &Hit, rng: &mut Rng) -> Color {
+    let material = &self.scene.object(hit.object).material;
+    let to_viewer = -ray.direction;
+    self.direct_light(hit, rng, |to_light| material.reflectance(hit.normal, to_viewer, to_light))
+  }
+
+  /// Sums the RGB light arriving at `hit` from every light that is not
+  /// shadowed, weighted by the angle of incidence. Area lights average
+  /// several shadow rays spread over their surface, giving soft shadows.
+  pub fn irradiance(&self, hit: &Hit, rng: &mut Rng) -> Color {
+    self.direct_light(hit, rng, |_| Color::WHITE)
+  }
+
+  /// The irradiance at `hit` with the light from each direction scaled by
+  /// `reflectance`.
+  fn direct_light(&self, hit: &Hit, rng: &mut Rng, reflectance: impl Fn(Vector3D) -> Color) -> Color {
+    let mut irradiance = Color::BLACK;
+
+    for light in &self.scene.lights {
+      let samples = light.samples().max(1);
+      let mut sum = Color::BLACK;
+      for i in 0..samples {
+        let sample = match light.sample(hit.point, sampling::stratified(i, samples, rng)) {
+          Some(sample) => sample,
+          None => continue,
+        };
+        let cos_theta = hit.normal.dot(&sample.direction);
+        if cos_theta <= 0.0 { continue; }
+
+        // anything past the light can't cast a shadow from it
+        let shadow_ray = Ray::spawn(hit.point, hit.normal, sample.direction);
+        if self.scene.occluded(&shadow_ray, sample.distance) { continue; }
+
+        sum += reflectance(sample.direction) * sample.irradiance * cos_theta;
+      }
+      irradiance += sum * (1.0 / samples as f32);
+    }
+
+    irradiance
+  }
+}
+
+/// Renders `scene` into a new image using `settings`.
+pub fn render(scene: &Scene, settings: &RenderSettings) -> Image {
+  Renderer::new(scene, settings).render()
+}
@@ -0,0 +1,144 @@
+//! Warps uniform random numbers into the points and directions that pixels,
+//! lenses and lights are sampled with.
+
+use crate::random::Rng;
+use crate::vector::Vector3D;
+
+/// A jittered point in cell `index` of `count` cells covering the unit
+/// square. The cells are laid out in `floor(sqrt(count))` rows, some rows
+/// getting one cell more when `count` isn't a square.
+pub fn stratified(index: usize, count: usize, rng: &mut Rng) -> (f32, f32) {
+  let rows = ((count as f32).sqrt() as usize).max(1);
+  let mut start = 0;
+  for row in 0..rows {
+    let end = count * (row + 1) / rows;
+    if index < end {
+      let columns = end - start;
+      return (((index - start) as f32 + rng.next_f32()) / columns as f32, (row as f32 + rng.next_f32()) / rows as f32);
+    }
+    start = end;
+  }
+  (rng.next_f32(), rng.next_f32())
+}
+
+/// Shirley and Chiu's area preserving mapping of the unit square onto the
+/// unit disc.
+pub fn concentric_disc(s: f32, t: f32) -> (f32, f32) {
+  let (a, b) = (s * 2.0 - 1.0, t * 2.0 - 1.0);
+  if a == 0.0 && b == 0.0 {
+    return (0.0, 0.0);
+  }
+
+  let quarter = std::f32::consts::FRAC_PI_4;
+  let (r, theta) = if a.abs() > b.abs() { (a, quarter * (b / a)) } else { (b, 2.0 * quarter - quarter * (a / b)) };
+  (r * theta.cos(), r * theta.sin())
+}
+
+/// Two unit vectors perpendicular to the unit vector `n` and each other,
+/// using the branchless construction of Duff et al.
+pub fn orthonormal_basis(n: Vector3D) -> (Vector3D, Vector3D) {
+  let sign = 1f32.copysign(n.z);
+  let a = -1.0 / (sign + n.z);
+  let b = n.x * n.y * a;
+  (
+    Vector3D::new(1.0 + sign * n.x * n.x * a, sign * b, -sign * n.x),
+    Vector3D::new(b, sign + n.y * n.y * a, -n.y),
+  )
+}
+
+/// A unit vector picked uniformly by solid angle from the cone around the
+/// unit vector `axis` whose angular radius has a cosine of
+/// `1 - one_minus_cos_max`. Passing `1 - cos` keeps narrow cones precise.
+pub fn uniform_cone(axis: Vector3D, one_minus_cos_max: f32, (s, t): (f32, f32)) -> Vector3D {
+  let cos_theta = 1.0 - s * one_minus_cos_max;
+  let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+  let phi = std::f32::consts::TAU * t;
+  let (tangent, bitangent) = orthonormal_basis(axis);
+  (tangent * (sin_theta * phi.cos()) + bitangent * (sin_theta * phi.sin()) + axis * cos_theta).normalize()
+}
+
+/// A piecewise constant distribution over `[0, 1)` with one equally wide
+/// piece per weight, for picking pieces in proportion to their weights.
+#[derive(Debug, Clone)]
+pub struct Distribution1D {
+  /// Running sums of the weights scaled to end at 1, one more than there
+  /// are pieces.
+  cdf: Vec<f32>,
+  total: f32,
+}
+
+impl Distribution1D {
+  pub fn new(weights: &[f32]) -> Distribution1D {
+    let mut cdf = Vec::with_capacity(weights.len() + 1);
+    let mut sum = 0.0f64;
+    cdf.push(0.0);
+    for &weight in weights {
+      sum += weight.max(0.0) as f64;
+      cdf.push(sum as f32);
+    }
+    if sum > 0.0 {
+      for value in &mut cdf {
+        *value = (*value as f64 / sum) as f32;
+      }
+    }
+    Distribution1D { cdf, total: sum as f32 }
+  }
+
+  /// The sum of all weights. Nothing can be sampled when it's zero.
+  pub fn total(&self) -> f32 {
+    self.total
+  }
+
+  /// Maps the uniform number `u` to a piece, the position within `[0, 1)`
+  /// that lands in it and the probability density there.
+  pub fn sample(&self, u: f32) -> (usize, f32, f32) {
+    let pieces = self.cdf.len() - 1;
+    // the last running sum not above `u` starts the piece, which skips empty
+    // ones
+    let piece = (self.cdf.partition_point(|&c| c <= u).max(1) - 1).min(pieces - 1);
+    let (start, end) = (self.cdf[piece], self.cdf[piece + 1]);
+    let offset = if end > start { ((u - start) / (end - start)).min(1.0 - f32::EPSILON) } else { 0.5 };
+    (piece, (piece as f32 + offset) / pieces as f32, (end - start) * pieces as f32)
+  }
+
+  /// The probability density anywhere within `piece`.
+  pub fn pdf(&self, piece: usize) -> f32 {
+    (self.cdf[piece + 1] - self.cdf[piece]) * (self.cdf.len() - 1) as f32
+  }
+}
+
+/// A piecewise constant distribution over the unit square, with a grid of
+/// `width` by `height` weights given row by row. Rows are picked first, then
+/// a column within the row.
+#[derive(Debug, Clone)]
+pub struct Distribution2D {
+  rows: Vec<Distribution1D>,
+  marginal: Distribution1D,
+}
+
+impl Distribution2D {
+  pub fn new(weights: &[f32], width: usize, height: usize) -> Distribution2D {
+    let rows: Vec<_> = weights.chunks(width.max(1)).take(height).map(Distribution1D::new).collect();
+    let marginal = Distribution1D::new(&rows.iter().map(|row| row.total()).collect::<Vec<_>>());
+    Distribution2D { rows, marginal }
+  }
+
+  /// Maps uniform numbers to a point in the unit square and the probability
+  /// density there, or `None` when every weight is zero.
+  pub fn sample(&self, (s, t): (f32, f32)) -> Option<((f32, f32), f32)> {
+    if self.marginal.total() <= 0.0 { return None; }
+    let (row, y, row_pdf) = self.marginal.sample(t);
+    let (_, x, column_pdf) = self.rows[row].sample(s);
+    Some(((x, y), row_pdf * column_pdf))
+  }
+
+  /// The probability density of sampling the point `(x, y)`.
+  pub fn pdf(&self, (x, y): (f32, f32)) -> f32 {
+    if self.marginal.total() <= 0.0 { return 0.0; }
+    let height = self.rows.len();
+    let row = ((y * height as f32) as usize).min(height - 1);
+    let width = self.rows[row].cdf.len() - 1;
+    let column = ((x * width as f32) as usize).min(width - 1);
+    self.marginal.pdf(row) * self.rows[row].pdf(column)
+  }
+}
@@ -0,0 +1,102 @@
+use std::ops::Range;
+use std::sync::{Arc, OnceLock};
+
+use crate::bvh::{Bvh, BvhStats};
+use crate::camera::Camera;
+use crate::hit::Hit;
+use crate::light::Light;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::shape::Shape;
+use crate::triangle::TriangleMesh;
+
+/// A shape placed in the scene along with how it looks.
+#[derive(Debug)]
+pub struct Object {
+  pub shape: Box<dyn Shape>,
+  pub material: Material,
+}
+
+#[derive(Debug, Default)]
+pub struct Scene {
+  pub camera: Camera,
+  pub lights: Vec<Box<dyn Light>>,
+  objects: Vec<Object>,
+  /// Built on the first query after the objects last changed.
+  bvh: OnceLock<Bvh>,
+}
+
+impl Scene {
+  pub fn new(camera: Camera) -> Scene {
+    Scene { camera, lights: Vec::new(), objects: Vec::new(), bvh: OnceLock::new() }
+  }
+
+  /// Adds a shape to the scene, returning its object index. A plain `Color`
+  /// makes a diffuse material.
+  pub fn add<S: Shape + 'static, M: Into<Material>>(&mut self, shape: S, material: M) -> usize {
+    self.objects.push(Object { shape: Box::new(shape), material: material.into() });
+    self.bvh.take();
+    self.objects.len() - 1
+  }
+
+  pub fn add_light<L: Light + 'static>(&mut self, light: L) {
+    self.lights.push(Box::new(light));
+  }
+
+  pub fn objects(&self) -> &[Object] {
+    &self.objects
+  }
+
+  pub fn object(&self, index: usize) -> &Object {
+    &self.objects[index]
+  }
+
+  /// The acceleration structure over the scene's objects, building it first
+  /// if needed.
+  pub fn bvh(&self) -> &Bvh {
+    self.bvh.get_or_init(|| {
+      let bounds: Vec<_> = self.objects.iter().map(|object| object.shape.bounding_box()).collect();
+      Bvh::build(&bounds)
+    })
+  }
+
+  /// Traversal counters for all queries made against the scene so far.
+  pub fn stats(&self) -> BvhStats {
+    self.bvh().stats()
+  }
+
+  /// Adds every face of `mesh` to the scene, returning their indices.
+  pub fn add_mesh<M: Into<Material>>(&mut self, mesh: TriangleMesh, material: M) -> Range<usize> {
+    let material = material.into();
+    let start = self.objects.len();
+    let mesh = Arc::new(mesh);
+    for triangle in TriangleMesh::triangles(&mesh) {
+      self.add(triangle, material);
+    }
+    start..self.objects.len()
+  }
+
+  /// Finds the nearest object along `ray`, if any.
+  pub fn intersect(&self, ray: &Ray) -> Option<Hit> {
+    let nearest = self.bvh().intersect(ray, 0.0, f32::INFINITY, |index, t_max| {
+      self.objects[index].shape.intersect(ray, 0.0, t_max)
+    });
+
+    nearest.map(|(object, distance)| {
+      let shape = &self.objects[object].shape;
+      let point = ray.at(distance);
+      let normal = shape.normal(&point);
+      // flat surfaces are two sided, so always shade the side the ray is on
+      let normal = if normal.dot(&ray.direction) > 0.0 { -normal } else { normal };
+
+      Hit { distance, point, normal, uv: shape.uv(&point), object }
+    })
+  }
+
+  /// Whether anything blocks `ray` before it has travelled `t_max`.
+  pub fn occluded(&self, ray: &Ray, t_max: f32) -> bool {
+    self.bvh().any_hit(ray, 0.0, t_max, |index, t_max| {
+      self.objects[index].shape.intersect(ray, 0.0, t_max)
+    })
+  }
+}
@@ -0,0 +1,903 @@
+//! A small text format for describing scenes.
+//!
+//! A scene file is a list of blocks, each a keyword followed by properties in
+//! braces. Properties are a name followed by whitespace separated values, and
+//! `#` starts a comment that runs to the end of the line:
+//!
+//! ```text
+//! settings { width 800 height 600 samples 16 filter mitchell }
+//! camera { position 0 1 4 look_at 0 0 -5 up 0 1 0 fov 40 }
+//!
+//! material red { color 1 0 0 }
+//! material glossy { color 0.1 0.1 0.4 specular 0.5 0.5 0.5 shininess 64 }
+//!
+//! sphere { position 0 0 -5 radius 1 material red }
+//! plane  { point 0 -1 0 normal 0 1 0 color 0.8 0.8 0.8 }
+//! disc   { center 0 0 -4 normal 0 0 1 radius 0.5 color 1 1 1 }
+//! box    { min -1 -1 -6 max 1 1 -4 material glossy }
+//! mesh {
+//!   vertex 0 0 -3   vertex 1 0 -3   vertex 0 1 -3
+//!   face 0 1 2
+//!   color 1 1 1
+//! }
+//!
+//! light       { direction 0 -1 -1 color 1 0.95 0.9 intensity 0.5 }
+//! point_light { position 0 2 -4 color 1 0.9 0.8 intensity 4 }
+//! spot_light  {
+//!   position 0 3 -4   direction 0 -1 0   intensity 10
+//!   inner_angle 20   outer_angle 30
+//! }
+//! rect_light   { position 0 3 -4 edge_u 1 0 0 edge_v 0 0 1 intensity 2 samples 32 }
+//! disc_light   { center 0 3 -4 normal 0 -1 0 radius 0.5 intensity 2 }
+//! sphere_light { position 2 2 -4 radius 0.25 color 1 0.8 0.6 intensity 5 }
+//! environment  { file sky.hdr intensity 0.5 rotation 90 }
+//! sky          { turbidity 3 elevation 30 azimuth 45 }
+//! ```
+//!
+//! Meshes may also list one `normal x y z` and `uv u v` per vertex. Face
+//! indices count vertices from zero. The camera's `fov` is its vertical field
+//! of view in degrees. Giving it an `aperture` radius adds depth of field,
+//! focused at `focus_distance` or on the `look_at` point, and `blades` and
+//! `blade_rotation` shape the aperture into a polygon. `projection` may be
+//! `perspective`, `orthographic` (spanning `view_height` units vertically),
+//! `fisheye` or `equirectangular`. The `filter` setting is one of `box`,
+//! `tent`, `gaussian`, `mitchell` or `lanczos`.
+//!
+//! Colors are linear RGB triples of non-negative values. Every light takes a
+//! `color` too, white unless given, that is scaled by its `intensity`.
+//!
+//! Shapes take either a diffuse `color` or a named `material`. Materials may
+//! add a `specular` color for Blinn-Phong highlights, which are tighter the
+//! higher their `shininess` exponent, 32 unless given.
+//!
+//! `light` blocks are directional lights, infinitely far away. Given an
+//! `angle` they become a disc of that angular diameter in degrees, seen in
+//! the background and casting soft shadows with `samples` shadow rays. Point
+//! and spot light intensities are the irradiance one unit away, falling off
+//! with the square of the distance. Spot light cone angles are in degrees
+//! from the axis, fading out between `inner_angle` and `outer_angle`.
+//!
+//! Rectangle, disc and sphere lights have a surface that casts soft shadows
+//! and shows up in the image. Their `intensity` is how bright they look, and
+//! each shaded point averages `samples` shadow rays to them, 16 by default.
+//! Rectangles are centered on `position` with sides `edge_u` and `edge_v`,
+//! and like discs only shine from their front, towards `edge_u × edge_v` or
+//! `normal`.
+//!
+//! An `environment` block wraps a latitude-longitude `.hdr` or `.pfm` image
+//! `file`, named relative to the scene file, around the scene. It shows
+//! wherever rays escape and lights the scene with `samples` directions per
+//! shaded point, 16 by default, scaled by `intensity` and turned about the
+//! vertical axis by `rotation` degrees.
+//!
+//! A `sky` block adds daylight from Preetham's analytic sky model along with
+//! the sun as a directional light, both seen in the background. The sun is
+//! `elevation` degrees above the horizon and turned `azimuth` degrees from -z
+//! towards +x, and `turbidity` runs from 2 for clear air to 10 for haze.
+//! `intensity` is the sun's irradiance above the atmosphere, and `samples`
+//! applies to both the sun and the sky.
+
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::axis_aligned_box::AxisAlignedBox;
+use crate::camera::{Camera, Projection};
+use crate::color::Color;
+use crate::disc::Disc;
+use crate::environment::EnvironmentLight;
+use crate::filter::Filter;
+use crate::image::Image;
+use crate::light::{DirectionalLight, DiscLight, PointLight, RectangleLight, SphereLight, SpotLight};
+use crate::material::Material;
+use crate::plane::Plane;
+use crate::renderer::{RenderSettings, MAX_DIMENSION};
+use crate::scene::Scene;
+use crate::sky::{Sky, SkyLight};
+use crate::sphere::Sphere;
+use crate::triangle::TriangleMesh;
+use crate::vector::Vector3D;
+
+/// Everything a scene file describes.
+#[derive(Debug)]
+pub struct SceneDescription {
+  pub scene: Scene,
+  pub settings: RenderSettings,
+}
+
+/// A syntax or semantic error, pointing at the offending text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+  pub line: usize,
+  pub column: usize,
+  pub message: String,
+}
+
+impl fmt::Display for ParseError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}:{}: {}", self.line, self.column, self.message)
+  }
+}
+
+impl error::Error for ParseError {}
+
+#[derive(Debug)]
+pub enum LoadError {
+  Io(io::Error),
+  Parse(ParseError),
+}
+
+impl fmt::Display for LoadError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      LoadError::Io(err) => err.fmt(f),
+      LoadError::Parse(err) => err.fmt(f),
+    }
+  }
+}
+
+impl error::Error for LoadError {
+  fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+    match self {
+      LoadError::Io(err) => Some(err),
+      LoadError::Parse(err) => Some(err),
+    }
+  }
+}
+
+impl From<io::Error> for LoadError {
+  fn from(err: io::Error) -> LoadError { LoadError::Io(err) }
+}
+
+impl From<ParseError> for LoadError {
+  fn from(err: ParseError) -> LoadError { LoadError::Parse(err) }
+}
+
+/// Reads and parses the scene file at `path`.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<SceneDescription, LoadError> {
+  let path = path.as_ref();
+  let source = fs::read_to_string(path)?;
+  Ok(Parser::new(&source, path.parent().unwrap_or_else(|| Path::new(""))).parse()?)
+}
+
+/// Parses a scene description from `source`. Files it refers to are found
+/// relative to the current directory.
+pub fn parse(source: &str) -> Result<SceneDescription, ParseError> {
+  Parser::new(source, Path::new("")).parse()
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Token<'a> {
+  text: &'a str,
+  line: usize,
+  column: usize,
+}
+
+/// Splits `source` into braces and whitespace separated words, dropping
+/// comments. Also returns the position just past the end of the input.
+fn tokenize(source: &str) -> (Vec<Token<'_>>, (usize, usize)) {
+  let mut tokens = Vec::new();
+  let mut line = 1;
+  let mut column = 1;
+  // byte offset, line and column of the word being read
+  let mut word: Option<(usize, usize, usize)> = None;
+  let mut in_comment = false;
+
+  for (offset, c) in source.char_indices() {
+    let ends_word = in_comment || c == '#' || c == '{' || c == '}' || c.is_whitespace();
+    if ends_word {
+      if let Some((start, line, column)) = word.take() {
+        tokens.push(Token { text: &source[start..offset], line, column });
+      }
+    }
+
+    if in_comment {
+      in_comment = c != '\n';
+    } else if c == '#' {
+      in_comment = true;
+    } else if c == '{' || c == '}' {
+      tokens.push(Token { text: &source[offset..offset + 1], line, column });
+    } else if !c.is_whitespace() && word.is_none() {
+      word = Some((offset, line, column));
+    }
+
+    if c == '\n' {
+      line += 1;
+      column = 1;
+    } else {
+      column += 1;
+    }
+  }
+
+  if let Some((start, line, column)) = word {
+    tokens.push(Token { text: &source[start..], line, column });
+  }
+
+  (tokens, (line, column))
+}
+
+/// The properties every area light has.
+#[derive(Debug)]
+struct Emitter {
+  color: Color,
+  intensity: Option<f32>,
+  samples: usize,
+}
+
+impl Default for Emitter {
+  fn default() -> Emitter {
+    Emitter { color: Color::WHITE, intensity: None, samples: 16 }
+  }
+}
+
+struct Parser<'a> {
+  tokens: Vec<Token<'a>>,
+  position: usize,
+  /// Line and column just past the end of the input.
+  end: (usize, usize),
+  /// Where files named in the scene are looked up.
+  directory: &'a Path,
+  materials: HashMap<&'a str, Material>,
+}
+
+impl<'a> Parser<'a> {
+  fn new(source: &'a str, directory: &'a Path) -> Parser<'a> {
+    let (tokens, end) = tokenize(source);
+    Parser { tokens, position: 0, end, directory, materials: HashMap::new() }
+  }
+
+  fn parse(mut self) -> Result<SceneDescription, ParseError> {
+    let mut scene = Scene::new(Camera::default());
+    let mut settings = RenderSettings::default();
+
+    while let Some(token) = self.next() {
+      match token.text {
+        "settings" => self.settings(&mut settings)?,
+        "camera" => scene.camera = self.camera(token)?,
+        "material" => self.material()?,
+        "sphere" => {
+          let (sphere, material) = self.sphere(token)?;
+          scene.add(sphere, material);
+        },
+        "plane" => {
+          let (plane, material) = self.plane(token)?;
+          scene.add(plane, material);
+        },
+        "disc" => {
+          let (disc, material) = self.disc(token)?;
+          scene.add(disc, material);
+        },
+        "box" => {
+          let (aabox, material) = self.aabox(token)?;
+          scene.add(aabox, material);
+        },
+        "mesh" => {
+          let (mesh, material) = self.mesh(token)?;
+          scene.add_mesh(mesh, material);
+        },
+        "light" => scene.add_light(self.light(token)?),
+        "point_light" => scene.add_light(self.point_light(token)?),
+        "spot_light" => scene.add_light(self.spot_light(token)?),
+        "rect_light" => scene.add_light(self.rect_light(token)?),
+        "disc_light" => scene.add_light(self.disc_light(token)?),
+        "sphere_light" => scene.add_light(self.sphere_light(token)?),
+        "environment" => scene.add_light(self.environment(token)?),
+        "sky" => {
+          let (sky, samples) = self.sky(token)?;
+          scene.add_light(sky.sun(samples));
+          scene.add_light(SkyLight::new(sky, samples));
+        },
+        _ => return Err(error_at(&token, format!("expected a block such as `sphere` or `light`, found `{}`", token.text))),
+      }
+    }
+
+    Ok(SceneDescription { scene, settings })
+  }
+
+  fn settings(&mut self, settings: &mut RenderSettings) -> Result<(), ParseError> {
+    self.open()?;
+    while let Some(key) = self.property()? {
+      match key.text {
+        "width" => settings.width = self.dimension()?,
+        "height" => settings.height = self.dimension()?,
+        "samples" => settings.samples = self.positive_integer()?,
+        "filter" => {
+          let name = self.expect_word("a filter name")?;
+          settings.filter = Filter::from_name(name.text).ok_or_else(|| {
+            error_at(&name, format!("expected one of {}, found `{}`", Filter::NAMES.join(", "), name.text))
+          })?;
+        },
+        "threads" => settings.threads = self.integer()?,
+        "tile_size" => settings.tile_size = self.positive_integer()?,
+        _ => return Err(unknown_property(&key, "settings")),
+      }
+    }
+    Ok(())
+  }
+
+  fn camera(&mut self, block: Token<'a>) -> Result<Camera, ParseError> {
+    let mut camera = Camera::default();
+    let (mut target, mut focus_distance, mut fov, mut view_height) = (None, None, None, None);
+    self.open()?;
+    while let Some(key) = self.property()? {
+      match key.text {
+        "position" => camera.position = self.vector()?,
+        "look_at" => target = Some(self.vector()?),
+        "up" => camera.up = self.direction()?,
+        "projection" => {
+          let name = self.expect_word("a projection")?;
+          camera.projection = match name.text {
+            "perspective" => Projection::Perspective,
+            "orthographic" => Projection::Orthographic { height: 2.0 },
+            "fisheye" => Projection::Fisheye,
+            "equirectangular" => Projection::Equirectangular,
+            _ => return Err(error_at(&name, format!(
+              "expected perspective, orthographic, fisheye or equirectangular, found `{}`", name.text,
+            ))),
+          };
+        },
+        "fov" => {
+          let token = self.peek();
+          let value = self.number_between(0.0, 360.0)?;
+          fov = Some((token.unwrap(), value));
+        },
+        "view_height" => view_height = Some(self.positive_number()?),
+        "aperture" => camera.aperture = self.non_negative_number()?,
+        "focus_distance" => focus_distance = Some(self.positive_number()?),
+        "blades" => {
+          let token = self.peek();
+          camera.blades = match self.integer()? {
+            blades @ (0 | 3..=64) => blades as u32,
+            _ => return Err(error_at(&token.unwrap(), "expected 0 for a round aperture or 3 to 64 blades".to_string())),
+          };
+        },
+        "blade_rotation" => camera.blade_rotation = self.number()?,
+        _ => return Err(unknown_property(&key, "camera")),
+      }
+    }
+
+    if let Some((token, fov)) = fov {
+      if camera.projection == Projection::Perspective && fov >= 180.0 {
+        return Err(error_at(&token, "perspective cameras need a `fov` below 180 degrees".to_string()));
+      }
+      camera.fov = fov;
+    }
+    if let (Projection::Orthographic { height }, Some(view_height)) = (&mut camera.projection, view_height) {
+      *height = view_height;
+    }
+
+    // without a target keep looking down the negative z axis
+    camera.target = target.unwrap_or(camera.position + Vector3D::new(0.0, 0.0, -1.0));
+    if camera.target == camera.position {
+      return Err(error_at(&block, "camera `look_at` must differ from its `position`".to_string()));
+    }
+    // and focus on the target unless told otherwise
+    camera.focus_distance = focus_distance.unwrap_or_else(|| (camera.target - camera.position).magnitude());
+    Ok(camera)
+  }
+
+  fn material(&mut self) -> Result<(), ParseError> {
+    let name = self.expect_word("a material name")?;
+    if self.materials.contains_key(name.text) {
+      return Err(error_at(&name, format!("material `{}` is already defined", name.text)));
+    }
+
+    let (mut color, mut specular, mut shininess) = (None, Color::BLACK, 32.0);
+    self.open()?;
+    while let Some(key) = self.property()? {
+      match key.text {
+        "color" => color = Some(self.color()?),
+        "specular" => specular = self.color()?,
+        "shininess" => shininess = self.non_negative_number()?,
+        _ => return Err(unknown_property(&key, "material")),
+      }
+    }
+
+    let diffuse = color.ok_or_else(|| missing_property(&name, "material", "color"))?;
+    self.materials.insert(name.text, Material { diffuse, specular, shininess });
+    Ok(())
+  }
+
+  fn sphere(&mut self, block: Token<'a>) -> Result<(Sphere, Material), ParseError> {
+    let (mut position, mut radius, mut material) = (None, None, None);
+    self.open()?;
+    while let Some(key) = self.property()? {
+      match key.text {
+        "position" => position = Some(self.vector()?),
+        "radius" => radius = Some(self.positive_number()?),
+        _ => self.appearance(key, &mut material, "sphere")?,
+      }
+    }
+
+    Ok((
+      Sphere {
+        position: position.ok_or_else(|| missing_property(&block, "sphere", "position"))?,
+        radius: radius.ok_or_else(|| missing_property(&block, "sphere", "radius"))?,
+      },
+      material.ok_or_else(|| missing_property(&block, "sphere", "color` or `material"))?,
+    ))
+  }
+
+  fn plane(&mut self, block: Token<'a>) -> Result<(Plane, Material), ParseError> {
+    let (mut point, mut normal, mut material) = (None, None, None);
+    self.open()?;
+    while let Some(key) = self.property()? {
+      match key.text {
+        "point" => point = Some(self.vector()?),
+        "normal" => normal = Some(self.direction()?),
+        _ => self.appearance(key, &mut material, "plane")?,
+      }
+    }
+
+    Ok((
+      Plane {
+        point: point.ok_or_else(|| missing_property(&block, "plane", "point"))?,
+        normal: normal.ok_or_else(|| missing_property(&block, "plane", "normal"))?,
+      },
+      material.ok_or_else(|| missing_property(&block, "plane", "color` or `material"))?,
+    ))
+  }
+
+  fn disc(&mut self, block: Token<'a>) -> Result<(Disc, Material), ParseError> {
+    let (mut center, mut normal, mut radius, mut material) = (None, None, None, None);
+    self.open()?;
+    while let Some(key) = self.property()? {
+      match key.text {
+        "center" => center = Some(self.vector()?),
+        "normal" => normal = Some(self.direction()?),
+        "radius" => radius = Some(self.positive_number()?),
+        _ => self.appearance(key, &mut material, "disc")?,
+      }
+    }
+
+    Ok((
+      Disc {
+        center: center.ok_or_else(|| missing_property(&block, "disc", "center"))?,
+        normal: normal.ok_or_else(|| missing_property(&block, "disc", "normal"))?,
+        radius: radius.ok_or_else(|| missing_property(&block, "disc", "radius"))?,
+      },
+      material.ok_or_else(|| missing_property(&block, "disc", "color` or `material"))?,
+    ))
+  }
+
+  fn aabox(&mut self, block: Token<'a>) -> Result<(AxisAlignedBox, Material), ParseError> {
+    let (mut min, mut max, mut material) = (None, None, None);
+    self.open()?;
+    while let Some(key) = self.property()? {
+      match key.text {
+        "min" => min = Some((self.vector()?, key)),
+        "max" => max = Some(self.vector()?),
+        _ => self.appearance(key, &mut material, "box")?,
+      }
+    }
+
+    let (min, min_key) = min.ok_or_else(|| missing_property(&block, "box", "min"))?;
+    let max = max.ok_or_else(|| missing_property(&block, "box", "max"))?;
+    if min.x > max.x || min.y > max.y || min.z > max.z {
+      return Err(error_at(&min_key, "box `min` corner must not exceed its `max` corner".to_string()));
+    }
+
+    Ok((
+      AxisAlignedBox { min, max },
+      material.ok_or_else(|| missing_property(&block, "box", "color` or `material"))?,
+    ))
+  }
+
+  fn mesh(&mut self, block: Token<'a>) -> Result<(TriangleMesh, Material), ParseError> {
+    let mut mesh = TriangleMesh::default();
+    let mut faces = Vec::new();
+    let (mut normals, mut uvs) = (None, None);
+    let mut material = None;
+
+    self.open()?;
+    while let Some(key) = self.property()? {
+      match key.text {
+        "vertex" => mesh.positions.push(self.vector()?),
+        "normal" => {
+          normals.get_or_insert(key);
+          mesh.normals.push(self.direction()?);
+        },
+        "uv" => {
+          uvs.get_or_insert(key);
+          mesh.uvs.push((self.number()?, self.number()?));
+        },
+        "face" => faces.push([self.index()?, self.index()?, self.index()?]),
+        _ => self.appearance(key, &mut material, "mesh")?,
+      }
+    }
+
+    let vertices = mesh.positions.len();
+    for face in faces {
+      for (token, index) in &face {
+        if *index >= vertices {
+          return Err(error_at(token, format!("vertex {} does not exist, the mesh has {} vertices", index, vertices)));
+        }
+      }
+      mesh.indices.push([face[0].1, face[1].1, face[2].1]);
+    }
+
+    if let Some(key) = normals {
+      if mesh.normals.len() != vertices {
+        return Err(error_at(&key, format!("mesh has {} normals but {} vertices", mesh.normals.len(), vertices)));
+      }
+    }
+    if let Some(key) = uvs {
+      if mesh.uvs.len() != vertices {
+        return Err(error_at(&key, format!("mesh has {} uvs but {} vertices", mesh.uvs.len(), vertices)));
+      }
+    }
+    if mesh.indices.is_empty() {
+      return Err(missing_property(&block, "mesh", "face"));
+    }
+
+    Ok((mesh, material.ok_or_else(|| missing_property(&block, "mesh", "color` or `material"))?))
+  }
+
+  fn light(&mut self, block: Token<'a>) -> Result<DirectionalLight, ParseError> {
+    let (mut direction, mut intensity, mut color) = (None, None, Color::WHITE);
+    let (mut angle, mut samples) = (0.0, 16);
+    self.open()?;
+    while let Some(key) = self.property()? {
+      match key.text {
+        "direction" => direction = Some(self.direction()?),
+        "color" => color = self.color()?,
+        "intensity" => intensity = Some(self.non_negative_number()?),
+        "angle" => angle = self.number_within(0.0, 180.0)?,
+        "samples" => samples = self.positive_integer()?,
+        _ => return Err(unknown_property(&key, "light")),
+      }
+    }
+
+    Ok(DirectionalLight {
+      direction: direction.ok_or_else(|| missing_property(&block, "light", "direction"))?,
+      color,
+      intensity: intensity.ok_or_else(|| missing_property(&block, "light", "intensity"))?,
+      angle,
+      samples,
+    })
+  }
+
+  fn point_light(&mut self, block: Token<'a>) -> Result<PointLight, ParseError> {
+    let (mut position, mut intensity, mut color) = (None, None, Color::WHITE);
+    self.open()?;
+    while let Some(key) = self.property()? {
+      match key.text {
+        "position" => position = Some(self.vector()?),
+        "color" => color = self.color()?,
+        "intensity" => intensity = Some(self.non_negative_number()?),
+        _ => return Err(unknown_property(&key, "point_light")),
+      }
+    }
+
+    Ok(PointLight {
+      position: position.ok_or_else(|| missing_property(&block, "point_light", "position"))?,
+      color,
+      intensity: intensity.ok_or_else(|| missing_property(&block, "point_light", "intensity"))?,
+    })
+  }
+
+  fn spot_light(&mut self, block: Token<'a>) -> Result<SpotLight, ParseError> {
+    let (mut position, mut direction, mut intensity, mut color) = (None, None, None, Color::WHITE);
+    let (mut inner_angle, mut outer_angle) = (None, None);
+    self.open()?;
+    while let Some(key) = self.property()? {
+      match key.text {
+        "position" => position = Some(self.vector()?),
+        "direction" => direction = Some(self.direction()?),
+        "color" => color = self.color()?,
+        "intensity" => intensity = Some(self.non_negative_number()?),
+        "inner_angle" => inner_angle = Some(self.non_negative_number()?),
+        "outer_angle" => outer_angle = Some((key, self.number_between(0.0, 180.0)?)),
+        _ => return Err(unknown_property(&key, "spot_light")),
+      }
+    }
+
+    let (outer_key, outer_angle) = outer_angle.ok_or_else(|| missing_property(&block, "spot_light", "outer_angle"))?;
+    // a hard edged cone unless told otherwise
+    let inner_angle = inner_angle.unwrap_or(outer_angle);
+    if inner_angle > outer_angle {
+      return Err(error_at(&outer_key, "`outer_angle` must be at least `inner_angle`".to_string()));
+    }
+
+    Ok(SpotLight {
+      position: position.ok_or_else(|| missing_property(&block, "spot_light", "position"))?,
+      direction: direction.ok_or_else(|| missing_property(&block, "spot_light", "direction"))?,
+      color,
+      intensity: intensity.ok_or_else(|| missing_property(&block, "spot_light", "intensity"))?,
+      inner_angle,
+      outer_angle,
+    })
+  }
+
+  fn rect_light(&mut self, block: Token<'a>) -> Result<RectangleLight, ParseError> {
+    let (mut position, mut edge_u, mut edge_v) = (None, None, None);
+    let mut emitter = Emitter::default();
+    self.open()?;
+    while let Some(key) = self.property()? {
+      match key.text {
+        "position" => position = Some(self.vector()?),
+        "edge_u" => edge_u = Some(self.direction()?),
+        "edge_v" => edge_v = Some((key, self.direction()?)),
+        _ => self.emitter(key, &mut emitter, "rect_light")?,
+      }
+    }
+
+    let edge_u = edge_u.ok_or_else(|| missing_property(&block, "rect_light", "edge_u"))?;
+    let (edge_v_key, edge_v) = edge_v.ok_or_else(|| missing_property(&block, "rect_light", "edge_v"))?;
+    if edge_u.cross(&edge_v).magnitude() == 0.0 {
+      return Err(error_at(&edge_v_key, "`edge_u` and `edge_v` must not be parallel".to_string()));
+    }
+
+    Ok(RectangleLight {
+      position: position.ok_or_else(|| missing_property(&block, "rect_light", "position"))?,
+      edge_u,
+      edge_v,
+      color: emitter.color,
+      intensity: emitter.intensity.ok_or_else(|| missing_property(&block, "rect_light", "intensity"))?,
+      samples: emitter.samples,
+    })
+  }
+
+  fn disc_light(&mut self, block: Token<'a>) -> Result<DiscLight, ParseError> {
+    let (mut center, mut normal, mut radius) = (None, None, None);
+    let mut emitter = Emitter::default();
+    self.open()?;
+    while let Some(key) = self.property()? {
+      match key.text {
+        "center" => center = Some(self.vector()?),
+        "normal" => normal = Some(self.direction()?),
+        "radius" => radius = Some(self.positive_number()?),
+        _ => self.emitter(key, &mut emitter, "disc_light")?,
+      }
+    }
+
+    Ok(DiscLight {
+      center: center.ok_or_else(|| missing_property(&block, "disc_light", "center"))?,
+      normal: normal.ok_or_else(|| missing_property(&block, "disc_light", "normal"))?,
+      radius: radius.ok_or_else(|| missing_property(&block, "disc_light", "radius"))?,
+      color: emitter.color,
+      intensity: emitter.intensity.ok_or_else(|| missing_property(&block, "disc_light", "intensity"))?,
+      samples: emitter.samples,
+    })
+  }
+
+  fn sphere_light(&mut self, block: Token<'a>) -> Result<SphereLight, ParseError> {
+    let (mut position, mut radius) = (None, None);
+    let mut emitter = Emitter::default();
+    self.open()?;
+    while let Some(key) = self.property()? {
+      match key.text {
+        "position" => position = Some(self.vector()?),
+        "radius" => radius = Some(self.positive_number()?),
+        _ => self.emitter(key, &mut emitter, "sphere_light")?,
+      }
+    }
+
+    Ok(SphereLight {
+      position: position.ok_or_else(|| missing_property(&block, "sphere_light", "position"))?,
+      radius: radius.ok_or_else(|| missing_property(&block, "sphere_light", "radius"))?,
+      color: emitter.color,
+      intensity: emitter.intensity.ok_or_else(|| missing_property(&block, "sphere_light", "intensity"))?,
+      samples: emitter.samples,
+    })
+  }
+
+  fn environment(&mut self, block: Token<'a>) -> Result<EnvironmentLight, ParseError> {
+    let (mut image, mut intensity, mut rotation, mut samples) = (None, 1.0, 0.0, 16);
+    self.open()?;
+    while let Some(key) = self.property()? {
+      match key.text {
+        "file" => image = Some(self.image()?),
+        "intensity" => intensity = self.non_negative_number()?,
+        "rotation" => rotation = self.number()?,
+        "samples" => samples = self.positive_integer()?,
+        _ => return Err(unknown_property(&key, "environment")),
+      }
+    }
+
+    let image = image.ok_or_else(|| missing_property(&block, "environment", "file"))?;
+    Ok(EnvironmentLight::new(image, intensity, rotation, samples))
+  }
+
+  fn sky(&mut self, block: Token<'a>) -> Result<(Sky, usize), ParseError> {
+    let mut sky = Sky { turbidity: 3.0, elevation: 0.0, azimuth: 0.0, intensity: 1.0 };
+    let (mut elevation, mut samples) = (None, 16);
+    self.open()?;
+    while let Some(key) = self.property()? {
+      match key.text {
+        "turbidity" => sky.turbidity = self.number_within(2.0, 10.0)?,
+        "elevation" => elevation = Some(self.number_within(0.0, 90.0)?),
+        "azimuth" => sky.azimuth = self.number()?,
+        "intensity" => sky.intensity = self.non_negative_number()?,
+        "samples" => samples = self.positive_integer()?,
+        _ => return Err(unknown_property(&key, "sky")),
+      }
+    }
+
+    sky.elevation = elevation.ok_or_else(|| missing_property(&block, "sky", "elevation"))?;
+    Ok((sky, samples))
+  }
+
+  /// Handles the `color`, `intensity` and `samples` properties shared by all
+  /// area lights.
+  fn emitter(&mut self, key: Token<'a>, emitter: &mut Emitter, block: &str) -> Result<(), ParseError> {
+    match key.text {
+      "color" => emitter.color = self.color()?,
+      "intensity" => emitter.intensity = Some(self.non_negative_number()?),
+      "samples" => emitter.samples = self.positive_integer()?,
+      _ => return Err(unknown_property(&key, block)),
+    }
+    Ok(())
+  }
+
+  /// Handles the `color` and `material` properties shared by all shapes.
+  fn appearance(&mut self, key: Token<'a>, material: &mut Option<Material>, block: &str) -> Result<(), ParseError> {
+    match key.text {
+      "color" => *material = Some(self.color()?.into()),
+      "material" => {
+        let name = self.expect_word("a material name")?;
+        match self.materials.get(name.text) {
+          Some(named) => *material = Some(*named),
+          None => return Err(error_at(&name, format!("material `{}` has not been defined", name.text))),
+        }
+      },
+      _ => return Err(unknown_property(&key, block)),
+    }
+    Ok(())
+  }
+
+  fn next(&mut self) -> Option<Token<'a>> {
+    let token = self.tokens.get(self.position).copied();
+    self.position += 1;
+    token
+  }
+
+  fn peek(&self) -> Option<Token<'a>> {
+    self.tokens.get(self.position).copied()
+  }
+
+  fn end_of_input(&self, expected: &str) -> ParseError {
+    ParseError { line: self.end.0, column: self.end.1, message: format!("expected {}, found the end of the file", expected) }
+  }
+
+  fn open(&mut self) -> Result<(), ParseError> {
+    match self.next() {
+      Some(token) if token.text == "{" => Ok(()),
+      Some(token) => Err(error_at(&token, format!("expected `{{`, found `{}`", token.text))),
+      None => Err(self.end_of_input("`{`")),
+    }
+  }
+
+  /// The next property name in a block, or `None` once the block closes.
+  fn property(&mut self) -> Result<Option<Token<'a>>, ParseError> {
+    match self.next() {
+      Some(token) if token.text == "}" => Ok(None),
+      Some(token) if token.text == "{" => Err(error_at(&token, "expected a property name, found `{`".to_string())),
+      Some(token) => Ok(Some(token)),
+      None => Err(self.end_of_input("`}`")),
+    }
+  }
+
+  fn expect_word(&mut self, expected: &str) -> Result<Token<'a>, ParseError> {
+    match self.peek() {
+      Some(token) if token.text != "{" && token.text != "}" => {
+        self.position += 1;
+        Ok(token)
+      },
+      Some(token) => Err(error_at(&token, format!("expected {}, found `{}`", expected, token.text))),
+      None => Err(self.end_of_input(expected)),
+    }
+  }
+
+  fn number(&mut self) -> Result<f32, ParseError> {
+    let token = self.expect_word("a number")?;
+    match token.text.parse::<f32>() {
+      Ok(value) if value.is_finite() => Ok(value),
+      _ => Err(error_at(&token, format!("expected a number, found `{}`", token.text))),
+    }
+  }
+
+  /// A number strictly between `min` and `max`.
+  fn number_between(&mut self, min: f32, max: f32) -> Result<f32, ParseError> {
+    let token = self.peek();
+    match self.number()? {
+      value if value > min && value < max => Ok(value),
+      _ => Err(error_at(&token.unwrap(), format!("expected a number between {} and {}", min, max))),
+    }
+  }
+
+  /// A number from `min` to `max`, both included.
+  fn number_within(&mut self, min: f32, max: f32) -> Result<f32, ParseError> {
+    let token = self.peek();
+    match self.number()? {
+      value if value >= min && value <= max => Ok(value),
+      _ => Err(error_at(&token.unwrap(), format!("expected a number from {} to {}", min, max))),
+    }
+  }
+
+  fn positive_number(&mut self) -> Result<f32, ParseError> {
+    let token = self.peek();
+    match self.number()? {
+      value if value > 0.0 => Ok(value),
+      _ => Err(error_at(&token.unwrap(), "expected a number greater than zero".to_string())),
+    }
+  }
+
+  fn non_negative_number(&mut self) -> Result<f32, ParseError> {
+    let token = self.peek();
+    match self.number()? {
+      value if value >= 0.0 => Ok(value),
+      _ => Err(error_at(&token.unwrap(), "expected a number of at least 0".to_string())),
+    }
+  }
+
+  fn integer(&mut self) -> Result<usize, ParseError> {
+    let token = self.expect_word("a whole number")?;
+    token.text.parse::<usize>()
+      .map_err(|_| error_at(&token, format!("expected a whole number, found `{}`", token.text)))
+  }
+
+  fn positive_integer(&mut self) -> Result<usize, ParseError> {
+    let token = self.peek();
+    match self.integer()? {
+      0 => Err(error_at(&token.unwrap(), "expected a number greater than zero".to_string())),
+      value => Ok(value),
+    }
+  }
+
+  /// An image width or height, no larger than the command line allows.
+  fn dimension(&mut self) -> Result<usize, ParseError> {
+    let token = self.peek();
+    match self.integer()? {
+      value if (1..=MAX_DIMENSION).contains(&value) => Ok(value),
+      _ => Err(error_at(&token.unwrap(), format!("expected a number from 1 to {}", MAX_DIMENSION))),
+    }
+  }
+
+  fn index(&mut self) -> Result<(Token<'a>, usize), ParseError> {
+    let token = self.peek();
+    let value = self.integer()?;
+    Ok((token.unwrap(), value))
+  }
+
+  fn vector(&mut self) -> Result<Vector3D, ParseError> {
+    Ok(Vector3D::new(self.number()?, self.number()?, self.number()?))
+  }
+
+  /// An image file named relative to the scene.
+  fn image(&mut self) -> Result<Image, ParseError> {
+    let token = self.expect_word("a file name")?;
+    let path = self.directory.join(token.text);
+    Image::load(&path).map_err(|err| error_at(&token, format!("could not load `{}`: {}", path.display(), err)))
+  }
+
+  /// Linear RGB values, none of which may be negative.
+  fn color(&mut self) -> Result<Color, ParseError> {
+    Ok(Color::new(self.non_negative_number()?, self.non_negative_number()?, self.non_negative_number()?))
+  }
+
+  /// A vector that must have a length, such as a normal or light direction.
+  fn direction(&mut self) -> Result<Vector3D, ParseError> {
+    let token = self.peek();
+    let vector = self.vector()?;
+    if vector.magnitude() == 0.0 {
+      return Err(error_at(&token.unwrap(), "direction must not be zero".to_string()));
+    }
+    Ok(vector)
+  }
+}
+
+fn error_at(token: &Token, message: String) -> ParseError {
+  ParseError { line: token.line, column: token.column, message }
+}
+
+fn unknown_property(key: &Token, block: &str) -> ParseError {
+  error_at(key, format!("unknown {} property `{}`", block, key.text))
+}
+
+fn missing_property(block: &Token, kind: &str, property: &str) -> ParseError {
+  error_at(block, format!("{} is missing `{}`", kind, property))
+}
@@ -0,0 +1,25 @@
+use std::fmt::Debug;
+
+use crate::bounding_box::BoundingBox;
+use crate::ray::Ray;
+use crate::vector::Vector3D;
+
+/// Geometry that can be placed in a `Scene`. Implement this to add new kinds
+/// of primitives without touching the renderer.
+pub trait Shape: Debug + Send + Sync {
+  /// The nearest distance along `ray` at which it crosses the surface within
+  /// the open interval `(t_min, t_max)`.
+  fn intersect(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<f32>;
+
+  /// The outward unit normal at `point`, a point on the surface.
+  fn normal(&self, point: &Vector3D) -> Vector3D;
+
+  /// A box enclosing the whole shape. Unbounded shapes return
+  /// `BoundingBox::infinite()`.
+  fn bounding_box(&self) -> BoundingBox;
+
+  /// Texture coordinates at `point`, for shapes that carry them.
+  fn uv(&self, _point: &Vector3D) -> Option<(f32, f32)> {
+    None
+  }
+}
@@ -0,0 +1,197 @@
+//! The analytic daylight model of Preetham, Shirley and Smits, "A Practical
+//! Analytic Model for Daylight" (SIGGRAPH 1999).
+
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use crate::color::Color;
+use crate::environment::{lat_long_direction, EnvironmentLight};
+use crate::image::Image;
+use crate::light::{DirectionalLight, Light, LightSample};
+use crate::vector::Vector3D;
+
+/// Angular diameter of the sun as seen from the earth, in degrees.
+pub const SUN_ANGLE: f32 = 0.53;
+
+/// Converts sky luminance in kcd/m² to irradiance in units of the sun's
+/// above the atmosphere, about 128 klux.
+const LUMINANCE_SCALE: f32 = PI / 128.0;
+
+/// Size of the map the sky is baked into for importance sampling.
+const MAP_WIDTH: usize = 256;
+const MAP_HEIGHT: usize = 128;
+
+/// Clear sky daylight for a given sun position and haziness.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sky {
+  /// Haziness of the air, from 2 for a very clear sky to 10 for a hazy one.
+  pub turbidity: f32,
+  /// Height of the sun above the horizon in degrees.
+  pub elevation: f32,
+  /// Compass direction of the sun in degrees, turning from -z towards +x.
+  pub azimuth: f32,
+  /// Irradiance of the sun above the atmosphere, which also sets how bright
+  /// the sky is.
+  pub intensity: f32,
+}
+
+/// The five coefficients of the Perez sky luminance distribution.
+type Perez = [f32; 5];
+
+impl Sky {
+  /// Unit vector pointing towards the sun.
+  pub fn sun_direction(&self) -> Vector3D {
+    let (elevation, azimuth) = (self.elevation.to_radians(), self.azimuth.to_radians());
+    Vector3D::new(azimuth.sin() * elevation.cos(), elevation.sin(), -azimuth.cos() * elevation.cos())
+  }
+
+  /// The sky seen looking along `direction`, not counting the sun itself.
+  /// The ground below the horizon is black.
+  pub fn radiance(&self, direction: Vector3D) -> Color {
+    let direction = direction.normalize();
+    if direction.y < 0.0 { return Color::BLACK; }
+
+    let theta_sun = self.sun_zenith();
+    let cos_gamma = direction.dot(&self.sun_direction()).clamp(-1.0, 1.0);
+    let (y_coefficients, x_coefficients, chroma_coefficients) = self.perez();
+    let (zenith_luminance, zenith_x, zenith_y) = self.zenith();
+
+    // each quantity is its zenith value scaled by how the Perez function
+    // varies from the zenith to this direction
+    let relative = |coefficients: &Perez| {
+      perez(coefficients, direction.y, cos_gamma) / perez(coefficients, 1.0, theta_sun.cos())
+    };
+    let luminance = zenith_luminance * relative(&y_coefficients);
+    let x = zenith_x * relative(&x_coefficients);
+    let y = zenith_y * relative(&chroma_coefficients);
+
+    xyy_to_rgb(x, y, luminance * LUMINANCE_SCALE * self.intensity)
+  }
+
+  /// The sun as a directional light, dimmed and reddened by the air it
+  /// shines through.
+  pub fn sun(&self, samples: usize) -> DirectionalLight {
+    DirectionalLight {
+      direction: -self.sun_direction(),
+      color: self.sun_transmittance(),
+      intensity: self.intensity,
+      angle: SUN_ANGLE,
+      samples,
+    }
+  }
+
+  /// Zenith angle of the sun in radians, kept above the horizon where the
+  /// model holds.
+  fn sun_zenith(&self) -> f32 {
+    (FRAC_PI_2 - self.elevation.to_radians()).clamp(0.0, FRAC_PI_2 - 0.01)
+  }
+
+  /// The fraction of sunlight reaching the ground at the wavelengths of the
+  /// red, green and blue primaries, from Rayleigh and aerosol extinction.
+  fn sun_transmittance(&self) -> Color {
+    let theta = self.sun_zenith();
+    // Kasten's relative optical air mass
+    let air_mass = 1.0 / (theta.cos() + 0.15 * (93.885 - theta.to_degrees()).powf(-1.253));
+    // Ångström's turbidity coefficient, with α = 1.3
+    let beta = 0.04608365 * self.turbidity - 0.04586025;
+
+    let transmittance = |wavelength: f32| {
+      let rayleigh = 0.008735 * wavelength.powf(-4.08);
+      let aerosol = beta * wavelength.powf(-1.3);
+      (-(rayleigh + aerosol) * air_mass).exp()
+    };
+    // in micrometers
+    Color::new(transmittance(0.68), transmittance(0.55), transmittance(0.44))
+  }
+
+  /// Luminance in kcd/m² and chromaticity of the sky straight up.
+  fn zenith(&self) -> (f32, f32, f32) {
+    let t = self.turbidity;
+    let theta = self.sun_zenith();
+    let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta);
+    let luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+
+    let (t2, theta2, theta3) = (t * t, theta * theta, theta * theta * theta);
+    let x = t2 * (0.00166 * theta3 - 0.00375 * theta2 + 0.00209 * theta)
+      + t * (-0.02903 * theta3 + 0.06377 * theta2 - 0.03202 * theta + 0.00394)
+      + (0.11693 * theta3 - 0.21196 * theta2 + 0.06052 * theta + 0.25886);
+    let y = t2 * (0.00275 * theta3 - 0.00610 * theta2 + 0.00317 * theta)
+      + t * (-0.04214 * theta3 + 0.08970 * theta2 - 0.04153 * theta + 0.00516)
+      + (0.15346 * theta3 - 0.26756 * theta2 + 0.06670 * theta + 0.26688);
+
+    (luminance.max(0.0), x, y)
+  }
+
+  /// Perez coefficients for luminance and the two chromaticity coordinates.
+  fn perez(&self) -> (Perez, Perez, Perez) {
+    let t = self.turbidity;
+    (
+      [0.1787 * t - 1.4630, -0.3554 * t + 0.4275, -0.0227 * t + 5.3251, 0.1206 * t - 2.5771, -0.0670 * t + 0.3703],
+      [-0.0193 * t - 0.2592, -0.0665 * t + 0.0008, -0.0004 * t + 0.2125, -0.0641 * t - 0.8989, -0.0033 * t + 0.0452],
+      [-0.0167 * t - 0.2608, -0.0950 * t + 0.0092, -0.0079 * t + 0.2102, -0.0441 * t - 1.6537, -0.0109 * t + 0.0529],
+    )
+  }
+}
+
+/// The Perez distribution for a direction at `cos_theta` from the zenith
+/// and `cos_gamma` from the sun.
+fn perez([a, b, c, d, e]: &Perez, cos_theta: f32, cos_gamma: f32) -> f32 {
+  let gamma = cos_gamma.acos();
+  // directions along the horizon would divide by zero
+  (1.0 + a * (b / cos_theta.max(0.01)).exp()) * (1.0 + c * (d * gamma).exp() + e * cos_gamma * cos_gamma)
+}
+
+/// Converts chromaticity and luminance to linear sRGB.
+fn xyy_to_rgb(x: f32, y: f32, luminance: f32) -> Color {
+  if y <= 0.0 { return Color::BLACK; }
+  let (cx, cz) = (x / y * luminance, (1.0 - x - y) / y * luminance);
+  Color::new(
+    3.2406 * cx - 1.5372 * luminance - 0.4986 * cz,
+    -0.9689 * cx + 1.8758 * luminance + 0.0415 * cz,
+    0.0557 * cx - 0.2040 * luminance + 1.0570 * cz,
+  )
+  .map(|c| c.max(0.0))
+}
+
+/// A `Sky` lighting the scene from every direction above the horizon and
+/// filling in the background. The sun itself is a separate light, see
+/// `Sky::sun`.
+#[derive(Debug, Clone)]
+pub struct SkyLight {
+  sky: Sky,
+  /// The sky baked into a map, which picks the directions to sample.
+  map: EnvironmentLight,
+}
+
+impl SkyLight {
+  /// Lights the scene with `sky`, sampling `samples` directions of it at
+  /// every shaded point.
+  pub fn new(sky: Sky, samples: usize) -> SkyLight {
+    let mut image = Image::new(MAP_WIDTH, MAP_HEIGHT);
+    for y in 0..MAP_HEIGHT {
+      for x in 0..MAP_WIDTH {
+        let uv = ((x as f32 + 0.5) / MAP_WIDTH as f32, (y as f32 + 0.5) / MAP_HEIGHT as f32);
+        image.set_pixel(x, y, sky.radiance(lat_long_direction(uv)));
+      }
+    }
+
+    SkyLight { sky, map: EnvironmentLight::new(image, 1.0, 0.0, samples) }
+  }
+
+  pub fn sky(&self) -> &Sky {
+    &self.sky
+  }
+}
+
+impl Light for SkyLight {
+  fn sample(&self, point: Vector3D, u: (f32, f32)) -> Option<LightSample> {
+    self.map.sample(point, u)
+  }
+
+  fn samples(&self) -> usize {
+    self.map.samples
+  }
+
+  fn environment(&self, direction: Vector3D) -> Color {
+    self.sky.radiance(direction)
+  }
+}
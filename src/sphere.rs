@@ -0,0 +1,67 @@
+use crate::bounding_box::BoundingBox;
+use crate::ray::Ray;
+use crate::shape::Shape;
+use crate::vector::Vector3D;
+
+#[derive(Debug, Clone)]
+pub struct Sphere {
+  pub position: Vector3D,
+  pub radius: f32,
+}
+
+impl Sphere {
+  /// Distances along `ray` at which its line enters and exits the sphere,
+  /// in that order. Either may be negative when the sphere is behind or
+  /// around the ray's origin.
+  pub fn intersections(&self, ray: &Ray) -> Option<(f32, f32)> {
+    let oc = ray.origin - self.position;
+    let a = ray.direction.dot(&ray.direction);
+    let b = oc.dot(&ray.direction);
+    let c = oc.dot(&oc) - self.radius * self.radius;
+
+    // b^2 - ac loses all precision for small spheres far from the origin, so
+    // measure the distance from the center to the ray's line directly
+    let closest = oc - ray.direction * (b / a);
+    let discriminant = a * (self.radius * self.radius - closest.dot(&closest));
+    if discriminant < 0.0 { return None; }
+
+    let q = -b - discriminant.sqrt().copysign(b);
+    if q == 0.0 { return Some((0.0, 0.0)); }
+
+    let (t0, t1) = (c / q, q / a);
+    Some(if t0 < t1 { (t0, t1) } else { (t1, t0) })
+  }
+
+  /// The nearest distance along `ray` at which it crosses the sphere's
+  /// surface within the open interval `(t_min, t_max)`.
+  pub fn intersects(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<f32> {
+    let (t0, t1) = self.intersections(ray)?;
+
+    if t0 > t_min && t0 < t_max {
+      Some(t0)
+    } else if t1 > t_min && t1 < t_max {
+      Some(t1)
+    } else {
+      None
+    }
+  }
+
+  pub fn surface_normal(&self, hit_point: &Vector3D) -> Vector3D {
+    (*hit_point - self.position).normalize()
+  }
+}
+
+impl Shape for Sphere {
+  fn intersect(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<f32> {
+    self.intersects(ray, t_min, t_max)
+  }
+
+  fn normal(&self, point: &Vector3D) -> Vector3D {
+    self.surface_normal(point)
+  }
+
+  fn bounding_box(&self) -> BoundingBox {
+    let extent = Vector3D::new(self.radius, self.radius, self.radius);
+    BoundingBox::new(self.position - extent, self.position + extent)
+  }
+}
@@ -0,0 +1,263 @@
+//! The display transform that turns rendered radiance into 8-bit sRGB.
+
+use std::sync::OnceLock;
+
+use crate::color::Color;
+use crate::image::Image;
+use crate::random::Rng;
+
+/// Curves compressing linear radiance into the displayable `[0, 1]` range.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ToneMapper {
+  /// Cuts off everything brighter than 1.0.
+  Clamp,
+  /// `x / (1 + x)`, which never quite reaches white.
+  Reinhard,
+  /// Reinhard scaled so that `white` and above map to 1.0.
+  ExtendedReinhard { white: f32 },
+  /// Stephen Hill's fit of the ACES reference and sRGB output transforms.
+  Aces,
+  /// John Hable's filmic curve from Uncharted 2.
+  Hable,
+}
+
+impl ToneMapper {
+  /// Maps a linear color to linear display values in `[0, 1]`.
+  pub fn apply(self, color: Color) -> Color {
+    let color = color.map(|c| c.max(0.0));
+    let mapped = match self {
+      ToneMapper::Clamp => color,
+      ToneMapper::Reinhard => color.map(|x| x / (1.0 + x)),
+      ToneMapper::ExtendedReinhard { white } => {
+        let white_squared = white * white;
+        color.map(|x| x * (1.0 + x / white_squared) / (1.0 + x))
+      },
+      ToneMapper::Aces => aces_fitted(color),
+      ToneMapper::Hable => {
+        const EXPOSURE_BIAS: f32 = 2.0;
+        const WHITE: f32 = 11.2;
+        let scale = 1.0 / hable_partial(WHITE);
+        color.map(|x| hable_partial(x * EXPOSURE_BIAS) * scale)
+      },
+    };
+    mapped.clamp(0.0, 1.0)
+  }
+}
+
+fn aces_fitted(color: Color) -> Color {
+  // sRGB to the RRT working space, and back from the ODT's output
+  const INPUT: [[f32; 3]; 3] = [
+    [0.59719, 0.35458, 0.04823],
+    [0.07600, 0.90834, 0.01566],
+    [0.02840, 0.13383, 0.83777],
+  ];
+  const OUTPUT: [[f32; 3]; 3] = [
+    [1.60475, -0.53108, -0.07367],
+    [-0.10208, 1.10813, -0.00605],
+    [-0.00327, -0.07276, 1.07602],
+  ];
+  let multiply = |m: &[[f32; 3]; 3], c: Color| {
+    Color::new(
+      m[0][0] * c.r + m[0][1] * c.g + m[0][2] * c.b,
+      m[1][0] * c.r + m[1][1] * c.g + m[1][2] * c.b,
+      m[2][0] * c.r + m[2][1] * c.g + m[2][2] * c.b,
+    )
+  };
+
+  let fitted = multiply(&INPUT, color).map(|v| {
+    (v * (v + 0.0245786) - 0.000090537) / (v * (0.983729 * v + 0.432951) + 0.238081)
+  });
+  multiply(&OUTPUT, fitted)
+}
+
+fn hable_partial(x: f32) -> f32 {
+  const A: f32 = 0.15;
+  const B: f32 = 0.50;
+  const C: f32 = 0.10;
+  const D: f32 = 0.20;
+  const E: f32 = 0.02;
+  const F: f32 = 0.30;
+  (x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F) - E / F
+}
+
+/// The sRGB opto-electronic transfer function, encoding a linear value in
+/// `[0, 1]` for display.
+pub fn srgb_encode(linear: f32) -> f32 {
+  if linear <= 0.0031308 {
+    linear * 12.92
+  } else {
+    1.055 * linear.powf(1.0 / 2.4) - 0.055
+  }
+}
+
+/// Noise added before quantizing to 8 bits, trading banding in smooth
+/// gradients for a fine, even grain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dither {
+  None,
+  /// An 8x8 Bayer matrix.
+  Ordered,
+  /// A tiled 64x64 blue noise mask, without the Bayer cross-hatching.
+  BlueNoise,
+}
+
+impl Dither {
+  /// The quantization threshold in `[0, 1)` for the pixel at `(x, y)`.
+  pub fn threshold(self, x: usize, y: usize) -> f32 {
+    match self {
+      Dither::None => 0.5,
+      Dither::Ordered => (bayer_rank(x % 8, y % 8) as f32 + 0.5) / 64.0,
+      Dither::BlueNoise => {
+        let mask = blue_noise();
+        mask[(y % BLUE_NOISE_SIZE) * BLUE_NOISE_SIZE + x % BLUE_NOISE_SIZE]
+      },
+    }
+  }
+}
+
+/// The position of `(x, y)` in the recursive 8x8 Bayer ordering.
+fn bayer_rank(x: usize, y: usize) -> usize {
+  let mut rank = 0;
+  for bit in 0..3 {
+    let (bx, by) = ((x >> bit) & 1, (y >> bit) & 1);
+    // every level orders its quadrants top left, bottom right, top right,
+    // bottom left, with the finest level varying slowest
+    rank |= (2 * (bx ^ by) + by) << (2 * (2 - bit));
+  }
+  rank
+}
+
+const BLUE_NOISE_SIZE: usize = 64;
+
+/// Thresholds generated with Ulichney's void-and-cluster method, computed
+/// once on first use.
+fn blue_noise() -> &'static [f32] {
+  static MASK: OnceLock<Vec<f32>> = OnceLock::new();
+  MASK.get_or_init(|| {
+    let n = BLUE_NOISE_SIZE * BLUE_NOISE_SIZE;
+    let ranks = void_and_cluster(BLUE_NOISE_SIZE);
+    ranks.into_iter().map(|rank| (rank as f32 + 0.5) / n as f32).collect()
+  })
+}
+
+/// Ranks every cell of a `size` by `size` torus so that each prefix of the
+/// ranking is as evenly spread out as possible.
+fn void_and_cluster(size: usize) -> Vec<usize> {
+  const SIGMA: f32 = 1.5;
+  const RADIUS: isize = 6;
+  let n = size * size;
+
+  let mut kernel = Vec::new();
+  for dy in -RADIUS..=RADIUS {
+    for dx in -RADIUS..=RADIUS {
+      let distance_squared = (dx * dx + dy * dy) as f32;
+      kernel.push((dx, dy, (-distance_squared / (2.0 * SIGMA * SIGMA)).exp()));
+    }
+  }
+
+  // energy[i] measures how crowded the set cells around cell i are
+  let update = |energy: &mut [f32], cell: usize, sign: f32| {
+    let (x, y) = ((cell % size) as isize, (cell / size) as isize);
+    for &(dx, dy, weight) in &kernel {
+      let nx = (x + dx).rem_euclid(size as isize) as usize;
+      let ny = (y + dy).rem_euclid(size as isize) as usize;
+      energy[ny * size + nx] += sign * weight;
+    }
+  };
+  let tightest_cluster = |pattern: &[bool], energy: &[f32]| {
+    (0..n).filter(|&i| pattern[i]).fold(None, |best: Option<usize>, i| match best {
+      Some(b) if energy[b] >= energy[i] => Some(b),
+      _ => Some(i),
+    })
+  };
+  let largest_void = |pattern: &[bool], energy: &[f32]| {
+    (0..n).filter(|&i| !pattern[i]).fold(None, |best: Option<usize>, i| match best {
+      Some(b) if energy[b] <= energy[i] => Some(b),
+      _ => Some(i),
+    })
+  };
+
+  // a random starting pattern, relaxed by moving points from the most
+  // crowded spot to the emptiest until that stops changing anything
+  let mut rng = Rng::new(BLUE_NOISE_SIZE as u64);
+  let mut pattern = vec![false; n];
+  let mut energy = vec![0.0; n];
+  let mut ones = 0;
+  while ones < n / 10 {
+    let cell = rng.next_u32() as usize % n;
+    if !pattern[cell] {
+      pattern[cell] = true;
+      update(&mut energy, cell, 1.0);
+      ones += 1;
+    }
+  }
+
+  loop {
+    let cluster = tightest_cluster(&pattern, &energy).unwrap();
+    pattern[cluster] = false;
+    update(&mut energy, cluster, -1.0);
+
+    let void = largest_void(&pattern, &energy).unwrap();
+    pattern[void] = true;
+    update(&mut energy, void, 1.0);
+    if void == cluster { break; }
+  }
+
+  let mut ranks = vec![0; n];
+
+  // rank the initial points by removing the most crowded one each time
+  let (mut prototype, mut prototype_energy) = (pattern.clone(), energy.clone());
+  for rank in (0..ones).rev() {
+    let cluster = tightest_cluster(&prototype, &prototype_energy).unwrap();
+    prototype[cluster] = false;
+    update(&mut prototype_energy, cluster, -1.0);
+    ranks[cluster] = rank;
+  }
+
+  // and the rest by filling the emptiest space
+  for rank in ones..n {
+    let void = largest_void(&pattern, &energy).unwrap();
+    pattern[void] = true;
+    update(&mut energy, void, 1.0);
+    ranks[void] = rank;
+  }
+
+  ranks
+}
+
+/// Everything between the rendered radiance and the 8-bit values written to
+/// PNG and PPM files.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisplayTransform {
+  /// Scales radiance by `2^exposure` before tone mapping.
+  pub exposure: f32,
+  pub tone_mapper: ToneMapper,
+  pub dither: Dither,
+}
+
+impl Default for DisplayTransform {
+  fn default() -> DisplayTransform {
+    DisplayTransform { exposure: 0.0, tone_mapper: ToneMapper::Clamp, dither: Dither::None }
+  }
+}
+
+impl DisplayTransform {
+  /// Maps linear radiance to sRGB encoded values in `[0, 1]`.
+  pub fn apply(&self, color: Color) -> Color {
+    let mapped = self.tone_mapper.apply(color * self.exposure.exp2());
+    mapped.map(srgb_encode)
+  }
+
+  /// Quantizes the whole image to 8-bit sRGB, three bytes per pixel.
+  pub fn to_rgb8(&self, image: &Image) -> Vec<u8> {
+    let mut rgb = Vec::with_capacity(image.pixels.len() * 3);
+    for (i, &pixel) in image.pixels.iter().enumerate() {
+      let threshold = self.dither.threshold(i % image.width, i / image.width);
+      let encoded = self.apply(pixel);
+      for c in 0..3 {
+        rgb.push((encoded[c] * 255.0 + threshold).floor().clamp(0.0, 255.0) as u8);
+      }
+    }
+    rgb
+  }
+}
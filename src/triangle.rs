@@ -0,0 +1,153 @@
+use std::sync::Arc;
+
+use crate::bounding_box::BoundingBox;
+use crate::ray::Ray;
+use crate::shape::Shape;
+use crate::vector::Vector3D;
+
+/// Indexed triangle geometry. Vertex attributes are shared between all of the
+/// triangles that reference them; `normals` and `uvs` are either empty or
+/// hold one entry per position.
+#[derive(Debug, Clone, Default)]
+pub struct TriangleMesh {
+  pub positions: Vec<Vector3D>,
+  pub normals: Vec<Vector3D>,
+  pub uvs: Vec<(f32, f32)>,
+  pub indices: Vec<[usize; 3]>,
+}
+
+impl TriangleMesh {
+  /// Splits the mesh into one `Triangle` per face, all sharing its buffers.
+  pub fn triangles(mesh: &Arc<TriangleMesh>) -> Vec<Triangle> {
+    (0..mesh.indices.len()).map(|face| Triangle::new(mesh.clone(), face)).collect()
+  }
+}
+
+/// A single face of a `TriangleMesh`.
+#[derive(Debug, Clone)]
+pub struct Triangle {
+  pub mesh: Arc<TriangleMesh>,
+  pub face: usize,
+}
+
+impl Triangle {
+  pub fn new(mesh: Arc<TriangleMesh>, face: usize) -> Triangle {
+    Triangle { mesh, face }
+  }
+
+  pub fn vertices(&self) -> [Vector3D; 3] {
+    let [a, b, c] = self.mesh.indices[self.face];
+    [self.mesh.positions[a], self.mesh.positions[b], self.mesh.positions[c]]
+  }
+
+  /// Watertight ray/triangle intersection (Woop, Benthin and Wald, 2013).
+  /// Rays through an edge or vertex shared by neighbouring triangles always
+  /// hit at least one of them, so meshes render without cracks.
+  pub fn intersects(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<f32> {
+    let d = ray.direction;
+
+    // permute the axes so the ray travels mostly along z, keeping the
+    // winding consistent when that axis points backwards
+    let kz = if d.x.abs() > d.y.abs() {
+      if d.x.abs() > d.z.abs() { 0 } else { 2 }
+    } else if d.y.abs() > d.z.abs() { 1 } else { 2 };
+    let mut kx = (kz + 1) % 3;
+    let mut ky = (kx + 1) % 3;
+    if d[kz] < 0.0 { std::mem::swap(&mut kx, &mut ky); }
+
+    // shear so the ray points down +z from the origin
+    let sx = d[kx] / d[kz];
+    let sy = d[ky] / d[kz];
+    let sz = 1.0 / d[kz];
+
+    let [v0, v1, v2] = self.vertices();
+    let a = v0 - ray.origin;
+    let b = v1 - ray.origin;
+    let c = v2 - ray.origin;
+
+    let ax = a[kx] - sx * a[kz];
+    let ay = a[ky] - sy * a[kz];
+    let bx = b[kx] - sx * b[kz];
+    let by = b[ky] - sy * b[kz];
+    let cx = c[kx] - sx * c[kz];
+    let cy = c[ky] - sy * c[kz];
+
+    let mut u = cx * by - cy * bx;
+    let mut v = ax * cy - ay * cx;
+    let mut w = bx * ay - by * ax;
+
+    // exactly on an edge, redo the edge tests in double precision so both
+    // neighbours agree on the result
+    if u == 0.0 || v == 0.0 || w == 0.0 {
+      let (ax, ay, bx, by, cx, cy) = (ax as f64, ay as f64, bx as f64, by as f64, cx as f64, cy as f64);
+      u = (cx * by - cy * bx) as f32;
+      v = (ax * cy - ay * cx) as f32;
+      w = (bx * ay - by * ax) as f32;
+    }
+
+    if (u < 0.0 || v < 0.0 || w < 0.0) && (u > 0.0 || v > 0.0 || w > 0.0) { return None; }
+
+    let det = u + v + w;
+    if det == 0.0 { return None; }
+
+    let t = (u * sz * a[kz] + v * sz * b[kz] + w * sz * c[kz]) / det;
+    if t > t_min && t < t_max { Some(t) } else { None }
+  }
+
+  /// Barycentric weights of `point` relative to the triangle's vertices.
+  pub fn barycentric(&self, point: &Vector3D) -> (f32, f32, f32) {
+    let [v0, v1, v2] = self.vertices();
+    let n = (v1 - v0).cross(&(v2 - v0));
+    let area = n.dot(&n);
+
+    let b1 = (*point - v0).cross(&(v2 - v0)).dot(&n) / area;
+    let b2 = (v1 - v0).cross(&(*point - v0)).dot(&n) / area;
+    (1.0 - b1 - b2, b1, b2)
+  }
+
+  /// The interpolated vertex normal at `hit_point`, or the face normal when
+  /// the mesh has no normals.
+  pub fn surface_normal(&self, hit_point: &Vector3D) -> Vector3D {
+    if self.mesh.normals.is_empty() {
+      let [v0, v1, v2] = self.vertices();
+      return (v1 - v0).cross(&(v2 - v0)).normalize();
+    }
+
+    let [a, b, c] = self.mesh.indices[self.face];
+    let (w0, w1, w2) = self.barycentric(hit_point);
+    let normals = &self.mesh.normals;
+    (normals[a] * w0 + normals[b] * w1 + normals[c] * w2).normalize()
+  }
+
+  /// The interpolated texture coordinates at `hit_point`, if the mesh has any.
+  pub fn uv(&self, hit_point: &Vector3D) -> Option<(f32, f32)> {
+    if self.mesh.uvs.is_empty() { return None; }
+
+    let [a, b, c] = self.mesh.indices[self.face];
+    let (w0, w1, w2) = self.barycentric(hit_point);
+    let uvs = &self.mesh.uvs;
+    Some((
+      uvs[a].0 * w0 + uvs[b].0 * w1 + uvs[c].0 * w2,
+      uvs[a].1 * w0 + uvs[b].1 * w1 + uvs[c].1 * w2,
+    ))
+  }
+}
+
+impl Shape for Triangle {
+  fn intersect(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<f32> {
+    self.intersects(ray, t_min, t_max)
+  }
+
+  fn normal(&self, point: &Vector3D) -> Vector3D {
+    self.surface_normal(point)
+  }
+
+  fn bounding_box(&self) -> BoundingBox {
+    let [v0, v1, v2] = self.vertices();
+    BoundingBox::new(v0, v0).grow(&v1).grow(&v2)
+  }
+
+  fn uv(&self, point: &Vector3D) -> Option<(f32, f32)> {
+    Triangle::uv(self, point)
+  }
+}
@@ -0,0 +1,98 @@
+use std::ops::{Add, Index, Mul, Neg, Sub};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Vector3D {
+  pub x: f32,
+  pub y: f32,
+  pub z: f32,
+}
+
+impl Vector3D {
+  pub fn new(x: f32, y: f32, z: f32) -> Vector3D {
+    Vector3D { x, y, z }
+  }
+
+  pub fn dot(&self, other: &Vector3D) -> f32 {
+    self.x * other.x + self.y * other.y + self.z * other.z
+  }
+
+  pub fn cross(&self, other: &Vector3D) -> Vector3D {
+    Vector3D {
+      x: self.y * other.z - self.z * other.y,
+      y: self.z * other.x - self.x * other.z,
+      z: self.x * other.y - self.y * other.x,
+    }
+  }
+
+  pub fn magnitude(&self) -> f32 {
+    self.dot(self).sqrt()
+  }
+
+  pub fn normalize(&self) -> Vector3D {
+    let s = 1.0 / self.magnitude();
+    Vector3D { x: self.x * s, y: self.y * s, z: self.z * s }
+  }
+
+  /// Component-wise minimum.
+  pub fn min(&self, other: &Vector3D) -> Vector3D {
+    Vector3D { x: self.x.min(other.x), y: self.y.min(other.y), z: self.z.min(other.z) }
+  }
+
+  /// Component-wise maximum.
+  pub fn max(&self, other: &Vector3D) -> Vector3D {
+    Vector3D { x: self.x.max(other.x), y: self.y.max(other.y), z: self.z.max(other.z) }
+  }
+
+  pub fn clamp(&self, min: f32, max: f32) -> Vector3D {
+    Vector3D {
+      x: self.x.min(max).max(min),
+      y: self.y.min(max).max(min),
+      z: self.z.min(max).max(min),
+    }
+  }
+}
+
+impl Add for Vector3D {
+  type Output = Vector3D;
+
+  fn add(self, other: Vector3D) -> Vector3D {
+    Vector3D { x: self.x + other.x, y: self.y + other.y, z: self.z + other.z }
+  }
+}
+
+impl Sub for Vector3D {
+  type Output = Vector3D;
+
+  fn sub(self, other: Vector3D) -> Vector3D {
+    Vector3D { x: self.x - other.x, y: self.y - other.y, z: self.z - other.z }
+  }
+}
+
+impl Mul<f32> for Vector3D {
+  type Output = Vector3D;
+
+  fn mul(self, scalar: f32) -> Vector3D {
+    Vector3D { x: self.x * scalar, y: self.y * scalar, z: self.z * scalar }
+  }
+}
+
+impl Neg for Vector3D {
+  type Output = Vector3D;
+
+  fn neg(self) -> Vector3D {
+    Vector3D { x: -self.x, y: -self.y, z: -self.z }
+  }
+}
+
+impl Index<usize> for Vector3D {
+  type Output = f32;
+
+  fn index(&self, axis: usize) -> &f32 {
+    match axis {
+      0 => &self.x,
+      1 => &self.y,
+      2 => &self.z,
+      _ => panic!("axis {} is out of range for a Vector3D", axis),
+    }
+  }
+}
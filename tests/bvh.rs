@@ -0,0 +1,104 @@
+use trace::{Camera, Color, Ray, Scene, Shape, Sphere, Vector3D};
+
+/// Small deterministic generator so the scenes are the same on every run.
+struct Lcg(u64);
+
+impl Lcg {
+  fn next(&mut self) -> f32 {
+    self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+    (self.0 >> 40) as f32 / (1u64 << 24) as f32
+  }
+
+  fn range(&mut self, min: f32, max: f32) -> f32 {
+    min + (max - min) * self.next()
+  }
+}
+
+fn random_spheres(count: usize, rng: &mut Lcg) -> Vec<Sphere> {
+  (0..count)
+    .map(|_| Sphere {
+      position: Vector3D::new(rng.range(-50.0, 50.0), rng.range(-50.0, 50.0), rng.range(-150.0, -50.0)),
+      radius: rng.range(0.05, 0.5),
+    })
+    .collect()
+}
+
+fn random_ray(rng: &mut Lcg) -> Ray {
+  let direction = Vector3D::new(rng.range(-0.5, 0.5), rng.range(-0.5, 0.5), -1.0);
+  Ray::new(Vector3D::new(0.0, 0.0, 0.0), direction.normalize())
+}
+
+fn brute_force(spheres: &[Sphere], ray: &Ray) -> Option<(usize, f32)> {
+  let mut nearest = None;
+  let mut closest = f32::INFINITY;
+  for (index, sphere) in spheres.iter().enumerate() {
+    if let Some(t) = sphere.intersect(ray, 0.0, closest) {
+      closest = t;
+      nearest = Some((index, t));
+    }
+  }
+  nearest
+}
+
+#[test]
+fn matches_brute_force_queries() {
+  let mut rng = Lcg(7);
+  let spheres = random_spheres(5000, &mut rng);
+  let mut scene = Scene::new(Camera::default());
+  for sphere in &spheres {
+    scene.add(sphere.clone(), Color::WHITE);
+  }
+
+  let mut hits = 0;
+  for _ in 0..2000 {
+    let ray = random_ray(&mut rng);
+    let expected = brute_force(&spheres, &ray);
+    let actual = scene.intersect(&ray).map(|hit| (hit.object, hit.distance));
+    assert_eq!(actual, expected);
+
+    let occluded = scene.occluded(&ray, 100.0);
+    assert_eq!(occluded, expected.is_some_and(|(_, t)| t < 100.0));
+    hits += expected.is_some() as usize;
+  }
+
+  // make sure the comparison was not trivially all misses
+  assert!(hits > 100, "only {} rays hit anything", hits);
+}
+
+#[test]
+fn visits_far_fewer_nodes_than_primitives() {
+  let mut rng = Lcg(11);
+  let count = 100_000;
+  let mut scene = Scene::new(Camera::default());
+  for sphere in random_spheres(count, &mut rng) {
+    scene.add(sphere, Color::WHITE);
+  }
+
+  scene.bvh().reset_stats();
+  let rays = 1000;
+  for _ in 0..rays {
+    scene.intersect(&random_ray(&mut rng));
+  }
+
+  let stats = scene.stats();
+  assert_eq!(stats.rays, rays);
+  let tests_per_ray = stats.primitive_tests / rays;
+  let visits_per_ray = stats.node_visits / rays;
+  assert!(tests_per_ray < count as u64 / 100, "{} primitive tests per ray", tests_per_ray);
+  assert!(visits_per_ray < count as u64 / 100, "{} node visits per ray", visits_per_ray);
+}
+
+#[test]
+fn unbounded_shapes_are_always_tested() {
+  let mut scene = Scene::new(Camera::default());
+  scene.add(Sphere { position: Vector3D::new(0.0, 0.0, -5.0), radius: 1.0 }, Color::WHITE);
+  let ground = scene.add(
+    trace::Plane { point: Vector3D::new(0.0, -1.0, 0.0), normal: Vector3D::new(0.0, 1.0, 0.0) },
+    Color::WHITE,
+  );
+
+  let down = Ray::new(Vector3D::new(20.0, 5.0, 0.0), Vector3D::new(0.0, -1.0, 0.0));
+  assert_eq!(scene.intersect(&down).unwrap().object, ground);
+  assert!(scene.occluded(&down, 10.0));
+  assert!(!scene.occluded(&down, 5.0));
+}
@@ -0,0 +1,200 @@
+use trace::{render, Camera, Color, DirectionalLight, Projection, RenderSettings, Scene, Sphere, Vector3D};
+
+fn assert_close(a: Vector3D, b: Vector3D) {
+  assert!((a - b).magnitude() < 1e-5, "{:?} != {:?}", a, b);
+}
+
+#[test]
+fn default_camera_matches_the_original_image_plane() {
+  let camera = Camera::default();
+  for &(u, v, aspect) in &[(0.5, 0.5, 1.0), (0.0, 0.0, 4.0 / 3.0), (1.0, 0.25, 2.0), (0.3, 1.0, 0.5)] {
+    let ray = camera.ray(u, v, aspect).unwrap();
+    let expected = Vector3D::new((u * 2.0 - 1.0) * aspect, 1.0 - v * 2.0, -3.0).normalize();
+    assert_eq!(ray.origin, Vector3D::default());
+    assert_close(ray.direction, expected);
+  }
+}
+
+#[test]
+fn center_ray_points_at_the_target() {
+  let camera = Camera::look_at(Vector3D::new(3.0, 2.0, 1.0), Vector3D::new(-1.0, 0.0, 5.0), 60.0);
+  let ray = camera.ray(0.5, 0.5, 1.5).unwrap();
+  assert_eq!(ray.origin, camera.position);
+  assert_close(ray.direction, (camera.target - camera.position).normalize());
+}
+
+#[test]
+fn image_top_and_right_follow_up_vector() {
+  let mut camera = Camera::look_at(Vector3D::new(0.0, 0.0, 0.0), Vector3D::new(1.0, 0.0, 0.0), 90.0);
+  let top = camera.ray(0.5, 0.0, 1.0).unwrap().direction;
+  let right = camera.ray(1.0, 0.5, 1.0).unwrap().direction;
+  assert!(top.y > 0.5 && right.z > 0.5, "{:?} {:?}", top, right);
+
+  // rolled onto its side, and with an up vector that isn't perpendicular
+  camera.up = Vector3D::new(0.5, 0.0, -2.0);
+  assert!(camera.ray(0.5, 0.0, 1.0).unwrap().direction.z < -0.5);
+  assert!(camera.ray(1.0, 0.5, 1.0).unwrap().direction.y > 0.5);
+}
+
+#[test]
+fn field_of_view_is_vertical_and_in_degrees() {
+  let camera = Camera::look_at(Vector3D::new(0.0, 0.0, 0.0), Vector3D::new(0.0, 0.0, -1.0), 50.0);
+  let forward = Vector3D::new(0.0, 0.0, -1.0);
+
+  let top = camera.ray(0.5, 0.0, 2.0).unwrap().direction;
+  assert!((top.dot(&forward).acos().to_degrees() - 25.0).abs() < 1e-3);
+
+  // the horizontal extent scales with the aspect ratio
+  let side = camera.ray(0.0, 0.5, 2.0).unwrap().direction;
+  let expected = (25f32.to_radians().tan() * 2.0).atan().to_degrees();
+  assert!((side.dot(&forward).acos().to_degrees() - expected).abs() < 1e-3);
+}
+
+#[test]
+fn looking_along_up_still_produces_rays() {
+  let camera = Camera::look_at(Vector3D::new(0.0, 5.0, 0.0), Vector3D::new(0.0, 0.0, 0.0), 40.0);
+  let (right, up, forward) = camera.basis();
+  assert_close(forward, Vector3D::new(0.0, -1.0, 0.0));
+  assert!(right.dot(&forward).abs() < 1e-6 && up.dot(&forward).abs() < 1e-6 && right.dot(&up).abs() < 1e-6);
+
+  let ray = camera.ray(0.1, 0.9, 1.0).unwrap();
+  assert!(ray.direction.magnitude().is_finite());
+}
+
+fn lens_samples() -> impl Iterator<Item = (f32, f32)> {
+  (0..32).flat_map(|i| (0..32).map(move |j| ((i as f32 + 0.5) / 32.0, (j as f32 + 0.5) / 32.0)))
+}
+
+#[test]
+fn pinhole_ignores_the_lens_sample() {
+  let camera = Camera::default();
+  let ray = camera.lens_ray(0.2, 0.7, 1.5, (0.0, 0.99)).unwrap();
+  assert_eq!(ray.origin, camera.ray(0.2, 0.7, 1.5).unwrap().origin);
+  assert_eq!(ray.direction, camera.ray(0.2, 0.7, 1.5).unwrap().direction);
+}
+
+#[test]
+fn lens_rays_converge_on_the_focus_plane() {
+  let mut camera = Camera::look_at(Vector3D::new(1.0, 2.0, 3.0), Vector3D::new(1.0, 2.0, -2.0), 40.0);
+  camera.aperture = 0.5;
+  assert_eq!(camera.focus_distance, 5.0);
+
+  let pinhole = camera.ray(0.3, 0.6, 1.0).unwrap();
+  let focus = pinhole.at(5.0 / -pinhole.direction.z);
+  let mut spread = 0.0f32;
+  for lens in lens_samples() {
+    let ray = camera.lens_ray(0.3, 0.6, 1.0, lens).unwrap();
+    assert!((ray.origin - camera.position).magnitude() <= 0.5 + 1e-5);
+    assert!((ray.origin.z - 3.0).abs() < 1e-6, "lens must lie across the view direction");
+    assert_close(ray.at((focus - ray.origin).magnitude()), focus);
+    spread = spread.max((ray.origin - camera.position).magnitude());
+  }
+  assert!(spread > 0.45);
+}
+
+#[test]
+fn round_apertures_fill_the_disc_evenly() {
+  let camera = Camera::default();
+  let points: Vec<(f32, f32)> = lens_samples().map(|lens| camera.aperture_point(lens)).collect();
+  assert!(points.iter().all(|p| p.0.hypot(p.1) <= 1.0 + 1e-6));
+
+  // a quarter of an even distribution lies within half the radius
+  let inner = points.iter().filter(|p| p.0.hypot(p.1) < 0.5).count() as f32 / points.len() as f32;
+  assert!((inner - 0.25).abs() < 0.02, "{}", inner);
+  let mean = points.iter().fold((0.0, 0.0), |m, p| (m.0 + p.0, m.1 + p.1));
+  assert!(mean.0.abs() < 1e-3 * points.len() as f32 && mean.1.abs() < 1e-3 * points.len() as f32);
+}
+
+#[test]
+fn bladed_apertures_stay_inside_their_polygon() {
+  let camera = Camera { blades: 5, blade_rotation: 18.0, ..Camera::default() };
+
+  // a pentagon rotated by 18 degrees has a vertex straight up and a flat
+  // edge at the bottom, cos(36) from the center
+  let points: Vec<(f32, f32)> = lens_samples().map(|lens| camera.aperture_point(lens)).collect();
+  let bottom = points.iter().map(|p| p.1).fold(f32::INFINITY, f32::min);
+  let top = points.iter().map(|p| p.1).fold(f32::NEG_INFINITY, f32::max);
+  assert!(bottom >= -36f32.to_radians().cos() - 1e-5 && bottom < -0.78, "{}", bottom);
+  assert!(top <= 1.0 + 1e-5 && top > 0.9, "{}", top);
+
+  for blade in 0..5 {
+    let angle = (18.0 + 72.0 * blade as f32 + 36.0).to_radians();
+    let apothem = 36f32.to_radians().cos();
+    assert!(points.iter().all(|p| p.0 * angle.cos() + p.1 * angle.sin() <= apothem + 1e-5));
+  }
+}
+
+fn camera(projection: Projection, fov: f32) -> Camera {
+  Camera { projection, ..Camera::look_at(Vector3D::new(1.0, 1.0, 1.0), Vector3D::new(1.0, 1.0, 0.0), fov) }
+}
+
+#[test]
+fn orthographic_rays_are_parallel() {
+  let camera = camera(Projection::Orthographic { height: 4.0 }, 40.0);
+  let corner = camera.ray(0.0, 0.0, 1.5).unwrap();
+  let center = camera.ray(0.5, 0.5, 1.5).unwrap();
+  assert_eq!(corner.direction, center.direction);
+  assert_close(center.origin, camera.position);
+  assert_close(corner.origin, Vector3D::new(1.0 - 3.0, 1.0 + 2.0, 1.0));
+}
+
+#[test]
+fn fisheye_angle_grows_linearly_from_the_center() {
+  let camera = camera(Projection::Fisheye, 180.0);
+  let forward = Vector3D::new(0.0, 0.0, -1.0);
+  let angle = |u, v| camera.ray(u, v, 2.0).unwrap().direction.dot(&forward).acos().to_degrees();
+
+  assert!(angle(0.5, 0.5) < 1e-3);
+  assert!((angle(0.5, 0.25) - 45.0).abs() < 1e-3);
+  assert!((angle(0.5, 0.0) - 90.0).abs() < 1e-3);
+  // half the image height to the right of the center, in a 2:1 image
+  assert!((angle(0.75, 0.5) - 90.0).abs() < 1e-3);
+  assert!(camera.ray(0.5, 0.0, 2.0).unwrap().direction.y > 0.999);
+
+  // outside the image circle
+  assert!(camera.ray(0.0, 0.0, 2.0).is_none());
+  assert!(camera.ray(0.9, 0.5, 2.0).is_none());
+}
+
+#[test]
+fn equirectangular_covers_every_direction() {
+  let camera = camera(Projection::Equirectangular, 40.0);
+  let direction = |u, v| camera.ray(u, v, 2.0).unwrap().direction;
+
+  assert_close(direction(0.5, 0.5), Vector3D::new(0.0, 0.0, -1.0));
+  assert_close(direction(0.75, 0.5), Vector3D::new(1.0, 0.0, 0.0));
+  assert_close(direction(0.25, 0.5), Vector3D::new(-1.0, 0.0, 0.0));
+  assert_close(direction(0.0, 0.5), Vector3D::new(0.0, 0.0, 1.0));
+  assert_close(direction(0.3, 0.0), Vector3D::new(0.0, 1.0, 0.0));
+  assert_close(direction(0.6, 1.0), Vector3D::new(0.0, -1.0, 0.0));
+  for &(u, v) in &[(0.1, 0.2), (0.9, 0.7), (0.4, 0.45)] {
+    assert!((direction(u, v).magnitude() - 1.0).abs() < 1e-5);
+  }
+}
+
+#[test]
+fn depth_of_field_blurs_at_one_sample_per_pixel() {
+  // a white ball well in front of the focus plane, lit from the camera
+  let render_with = |aperture| {
+    let camera = Camera { aperture, focus_distance: 20.0, ..Camera::default() };
+    let mut scene = Scene::new(camera);
+    scene.add(Sphere { position: Vector3D::new(0.0, 0.0, -4.0), radius: 1.0 }, Color::WHITE);
+    scene.add_light(DirectionalLight {
+      direction: Vector3D::new(0.0, 0.0, -1.0),
+      color: Color::WHITE,
+      intensity: 1.0,
+      angle: 0.0,
+      samples: 1,
+    });
+    render(&scene, &RenderSettings { width: 48, height: 48, samples: 1, ..RenderSettings::default() })
+  };
+
+  let sharp = render_with(0.0);
+  let blurred = render_with(0.3);
+  // rays from across the lens reach the ball from pixels outside its sharp
+  // outline, and miss it from some inside
+  let lit = |color: Color| color.g > 0.0;
+  let spread = sharp.pixels.iter().zip(&blurred.pixels).filter(|&(&a, &b)| !lit(a) && lit(b)).count();
+  let holes = sharp.pixels.iter().zip(&blurred.pixels).filter(|&(&a, &b)| lit(a) && !lit(b)).count();
+  assert!(spread > 20 && holes > 20, "{} pixels spread out, {} holes", spread, holes);
+}
@@ -0,0 +1,93 @@
+use std::env;
+use std::fs;
+use std::process::{Command, Output};
+
+fn trace(args: &[&str]) -> Output {
+  Command::new(env!("CARGO_BIN_EXE_trace"))
+    .args(args)
+    .current_dir(env!("CARGO_MANIFEST_DIR"))
+    .output()
+    .expect("failed to run the trace binary")
+}
+
+fn stderr(output: &Output) -> String {
+  String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn help_succeeds() {
+  let output = trace(&["--help"]);
+  assert!(output.status.success());
+  assert!(String::from_utf8_lossy(&output.stdout).contains("trace render <scene>"));
+}
+
+#[test]
+fn renders_with_overridden_settings() {
+  let path = env::temp_dir().join(format!("trace-cli-{}.ppm", std::process::id()));
+  let output = trace(&[
+    "render", "scenes/spheres.txt", "-o", path.to_str().unwrap(),
+    "--width", "32", "--height=24", "--spp", "2", "--threads", "2", "-q",
+    "--white", "8", "--tonemap", "reinhard-extended",
+  ]);
+  assert!(output.status.success(), "{}", stderr(&output));
+
+  let image = fs::read(&path).unwrap();
+  fs::remove_file(&path).unwrap();
+  assert!(image.starts_with(b"P6 32 24 255\n"));
+  assert_eq!(image.len(), b"P6 32 24 255\n".len() + 32 * 24 * 3);
+}
+
+#[test]
+fn exr_output_includes_aov_layers() {
+  let path = env::temp_dir().join(format!("trace-cli-{}.exr", std::process::id()));
+  let output = trace(&[
+    "render", "scenes/spheres.txt", "-o", path.to_str().unwrap(),
+    "--width", "16", "--height", "12", "--exr-compression=rle", "--exr-float", "-q",
+  ]);
+  assert!(output.status.success(), "{}", stderr(&output));
+
+  let image = fs::read(&path).unwrap();
+  fs::remove_file(&path).unwrap();
+  assert!(image.starts_with(&[0x76, 0x2f, 0x31, 0x01]));
+  for name in &["depth.Z", "normal.X", "albedo.R", "id"] {
+    let name = format!("{}\0", name);
+    assert!(image.windows(name.len()).any(|w| w == name.as_bytes()), "missing {}", name);
+  }
+}
+
+#[test]
+fn invalid_arguments_exit_with_usage_errors() {
+  for args in &[
+    &[][..],
+    &["paint"][..],
+    &["render"][..],
+    &["render", "scenes/spheres.txt", "--width", "0"][..],
+    &["render", "scenes/spheres.txt", "--spp", "many"][..],
+    &["render", "scenes/spheres.txt", "--height"][..],
+    &["render", "scenes/spheres.txt", "--frobnicate"][..],
+    &["render", "scenes/spheres.txt", "other.txt"][..],
+    &["render", "scenes/spheres.txt", "--exr-compression", "lzma"][..],
+    &["render", "scenes/spheres.txt", "--tonemap", "sepia"][..],
+    &["render", "scenes/spheres.txt", "--white", "0"][..],
+    &["render", "scenes/spheres.txt", "--white", "8"][..],
+    &["render", "scenes/spheres.txt", "--white", "8", "--tonemap", "aces"][..],
+    &["render", "scenes/spheres.txt", "--spp", "1000000000000"][..],
+    &["render", "scenes/spheres.txt", "--exposure", "bright"][..],
+    &["render", "scenes/spheres.txt", "--dither", "random"][..],
+  ] {
+    let output = trace(args);
+    assert_eq!(output.status.code(), Some(2), "{:?}: {}", args, stderr(&output));
+    assert!(stderr(&output).starts_with("error: "));
+  }
+}
+
+#[test]
+fn missing_scenes_and_bad_outputs_fail() {
+  let output = trace(&["render", "scenes/does-not-exist.txt"]);
+  assert_eq!(output.status.code(), Some(1));
+  assert!(stderr(&output).contains("scenes/does-not-exist.txt"));
+
+  let output = trace(&["render", "scenes/spheres.txt", "-o", "frame.gif"]);
+  assert_eq!(output.status.code(), Some(1));
+  assert!(stderr(&output).contains("unsupported output format"));
+}
@@ -0,0 +1,127 @@
+use std::env;
+use std::f32::consts::PI;
+use std::fs;
+
+use trace::random::Rng;
+use trace::sampling::{Distribution1D, Distribution2D};
+use trace::scene_file;
+use trace::{
+  render, Camera, Color, EnvironmentLight, Image, Light, Plane, Projection, Ray, RenderSettings, Renderer, Scene, Vector3D,
+};
+
+fn map(width: usize, height: usize, pixel: impl Fn(usize, usize) -> Color) -> Image {
+  let mut image = Image::new(width, height);
+  for y in 0..height {
+    for x in 0..width {
+      image.set_pixel(x, y, pixel(x, y));
+    }
+  }
+  image
+}
+
+/// Irradiance on an upward facing floor at the origin.
+fn floor_irradiance(light: EnvironmentLight) -> Color {
+  let mut scene = Scene::new(Camera::default());
+  scene.add(Plane { point: Vector3D::default(), normal: Vector3D::new(0.0, 1.0, 0.0) }, Color::WHITE);
+  scene.add_light(light);
+
+  let hit = scene.intersect(&Ray::new(Vector3D::new(0.0, 1.0, 0.0), Vector3D::new(0.0, -1.0, 0.0))).unwrap();
+  let settings = RenderSettings::default();
+  Renderer::new(&scene, &settings).irradiance(&hit, &mut Rng::new(7))
+}
+
+#[test]
+fn distributions_pick_pieces_in_proportion_to_their_weights() {
+  let distribution = Distribution1D::new(&[1.0, 0.0, 3.0]);
+  assert_eq!(distribution.total(), 4.0);
+  assert_eq!(distribution.sample(0.0).0, 0);
+  assert_eq!(distribution.sample(0.2), (0, 0.8 / 3.0, 0.75));
+  // the empty middle piece is never picked
+  assert_eq!(distribution.sample(0.25).0, 2);
+  assert_eq!(distribution.sample(0.999_999).0, 2);
+  assert_eq!(distribution.pdf(1), 0.0);
+  assert_eq!(distribution.pdf(2), 2.25);
+
+  let grid = Distribution2D::new(&[0.0, 0.0, 1.0, 3.0], 2, 2);
+  let ((x, y), pdf) = grid.sample((0.5, 0.5)).unwrap();
+  assert!(x >= 0.5 && y >= 0.5);
+  assert_eq!(pdf, grid.pdf((x, y)));
+  assert_eq!(grid.pdf((0.25, 0.25)), 0.0);
+  assert!(Distribution2D::new(&[0.0; 4], 2, 2).sample((0.5, 0.5)).is_none());
+}
+
+#[test]
+fn map_directions_round_trip() {
+  let light = EnvironmentLight::new(Image::new(4, 2), 1.0, 30.0, 1);
+  for &direction in &[Vector3D::new(0.3, 0.5, -0.8), Vector3D::new(-1.0, -0.2, 0.1), Vector3D::new(0.0, 0.1, 1.0)] {
+    let back = light.direction(light.uv(direction));
+    assert!((back - direction.normalize()).magnitude() < 1e-5, "{:?} came back as {:?}", direction, back);
+  }
+
+  let unrotated = EnvironmentLight::new(Image::new(4, 2), 1.0, 0.0, 1);
+  let (u, v) = unrotated.uv(Vector3D::new(0.0, 0.0, -1.0));
+  assert!((u - 0.5).abs() < 1e-6 && (v - 0.5).abs() < 1e-6);
+  assert!(unrotated.uv(Vector3D::new(0.0, 1.0, 0.0)).1 < 1e-6);
+}
+
+#[test]
+fn escaped_rays_see_the_map_as_an_equirectangular_camera_would() {
+  let image = map(16, 8, |x, y| Color::new(x as f32, y as f32, 1.0));
+  let mut scene = Scene::new(Camera { projection: Projection::Equirectangular, ..Camera::default() });
+  scene.add_light(EnvironmentLight::new(image.clone(), 2.0, 0.0, 1));
+
+  let settings = RenderSettings { width: 16, height: 8, ..RenderSettings::default() };
+  let rendered = render(&scene, &settings);
+  for (rendered, original) in rendered.pixels.iter().zip(&image.pixels) {
+    assert_eq!(*rendered, *original * 2.0);
+  }
+}
+
+#[test]
+fn an_evenly_lit_map_delivers_its_value() {
+  let irradiance = floor_irradiance(EnvironmentLight::new(map(32, 16, |_, _| Color::new(1.0, 0.5, 0.25)), 2.0, 0.0, 4096));
+  assert!((irradiance.r - 2.0).abs() < 0.05, "{:?}", irradiance);
+  assert!((irradiance.b / irradiance.r - 0.25).abs() < 1e-5);
+}
+
+#[test]
+fn importance_sampling_finds_small_bright_spots() {
+  // a single bright pixel straight up, which uniform sampling would almost
+  // never hit
+  let (width, height) = (256, 128);
+  let image = map(width, height, |x, y| if (x, y) == (128, 0) { Color::gray(1000.0) } else { Color::BLACK });
+  let light = EnvironmentLight::new(image, 1.0, 0.0, 64);
+
+  // the pixel's solid angle times its radiance, on a surface it is right above
+  let theta = PI / height as f32;
+  let solid_angle = 2.0 * PI / width as f32 * (1.0 - theta.cos());
+  let expected = 1000.0 / PI * solid_angle;
+
+  let irradiance = floor_irradiance(light).r;
+  assert!((irradiance - expected).abs() < expected * 0.02, "expected {}, got {}", expected, irradiance);
+
+  let sample = EnvironmentLight::new(map(4, 2, |x, y| Color::gray((x + y) as f32)), 1.0, 0.0, 1)
+    .sample(Vector3D::default(), (0.9, 0.9))
+    .unwrap();
+  assert_eq!(sample.distance, f32::INFINITY);
+}
+
+#[test]
+fn scene_files_load_maps_next_to_them() {
+  let directory = env::temp_dir().join(format!("trace-environment-{}", std::process::id()));
+  fs::create_dir_all(&directory).unwrap();
+  map(8, 4, |_, _| Color::gray(0.5)).save(directory.join("sky.pfm")).unwrap();
+  fs::write(directory.join("scene.txt"), "environment { file sky.pfm intensity 2 samples 4 }").unwrap();
+
+  let scene = scene_file::load(directory.join("scene.txt")).unwrap().scene;
+  assert_eq!(scene.lights[0].samples(), 4);
+  assert_eq!(scene.lights[0].environment(Vector3D::new(0.0, 0.0, -1.0)), Color::gray(1.0));
+
+  let err = scene_file::parse("environment { file missing.hdr }").unwrap_err();
+  assert_eq!((err.line, err.column), (1, 20));
+  assert!(err.message.contains("could not load `missing.hdr`"), "{}", err.message);
+  let err = scene_file::parse("environment { intensity 1 }").unwrap_err();
+  assert!(err.message.contains("missing `file`"));
+
+  fs::remove_dir_all(&directory).unwrap();
+}
@@ -0,0 +1,256 @@
+use std::convert::TryInto;
+
+use trace::format::exr::{f32_to_half, write_exr, Channel, Compression, PixelType};
+use trace::{Color, Image, Layer};
+
+fn half_to_f32(half: u16) -> f32 {
+  let sign = if half & 0x8000 != 0 { -1.0 } else { 1.0 };
+  let exponent = ((half >> 10) & 0x1f) as i32;
+  let mantissa = (half & 0x3ff) as f32;
+  match exponent {
+    0 => sign * mantissa * 2f32.powi(-24),
+    0x1f if mantissa == 0.0 => sign * f32::INFINITY,
+    0x1f => f32::NAN,
+    _ => sign * (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+  }
+}
+
+fn i32_at(data: &[u8], at: usize) -> i32 {
+  i32::from_le_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]])
+}
+
+/// The attributes of an EXR header by name, and the offset just past it.
+fn header(data: &[u8]) -> (Vec<(String, String, Vec<u8>)>, usize) {
+  assert_eq!(&data[..8], &[0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0]);
+  let mut attributes = Vec::new();
+  let mut position = 8;
+  let string = |position: &mut usize| {
+    let end = *position + data[*position..].iter().position(|&b| b == 0).unwrap();
+    let string = String::from_utf8(data[*position..end].to_vec()).unwrap();
+    *position = end + 1;
+    string
+  };
+
+  while data[position] != 0 {
+    let name = string(&mut position);
+    let kind = string(&mut position);
+    let size = i32_at(data, position) as usize;
+    attributes.push((name, kind, data[position + 4..position + 4 + size].to_vec()));
+    position += 4 + size;
+  }
+
+  (attributes, position + 1)
+}
+
+fn channel_names(attributes: &[(String, String, Vec<u8>)]) -> Vec<(String, i32)> {
+  let list = &attributes.iter().find(|a| a.0 == "channels").unwrap().2;
+  let mut channels = Vec::new();
+  let mut position = 0;
+  while list[position] != 0 {
+    let end = position + list[position..].iter().position(|&b| b == 0).unwrap();
+    channels.push((String::from_utf8(list[position..end].to_vec()).unwrap(), i32_at(list, end + 1)));
+    position = end + 17;
+  }
+  channels
+}
+
+/// Undoes the RLE scheme and the predictor in front of it.
+fn decode_rle(data: &[u8]) -> Vec<u8> {
+  let mut bytes = Vec::new();
+  let mut position = 0;
+  while position < data.len() {
+    let count = data[position] as i8;
+    position += 1;
+    if count < 0 {
+      bytes.extend_from_slice(&data[position..position + (-count) as usize]);
+      position += (-count) as usize;
+    } else {
+      bytes.extend(std::iter::repeat_n(data[position], count as usize + 1));
+      position += 1;
+    }
+  }
+
+  for i in 1..bytes.len() {
+    bytes[i] = bytes[i - 1].wrapping_add(bytes[i]).wrapping_sub(128);
+  }
+  let half = bytes.len().div_ceil(2);
+  (0..bytes.len()).map(|i| if i % 2 == 0 { bytes[i / 2] } else { bytes[half + i / 2] }).collect()
+}
+
+/// Reads every channel of a one-line-per-block file back as floats.
+fn decode(data: &[u8], width: usize, height: usize) -> Vec<(String, Vec<f32>)> {
+  let (attributes, mut position) = header(data);
+  let channels = channel_names(&attributes);
+  let line_size: usize = channels.iter().map(|c| if c.1 == 1 { 2 } else { 4 } * width).sum();
+
+  let mut planes: Vec<(String, Vec<f32>)> = channels.iter().map(|c| (c.0.clone(), Vec::new())).collect();
+  let mut offsets = Vec::new();
+  for _ in 0..height {
+    offsets.push(u64::from_le_bytes(data[position..position + 8].try_into().unwrap()) as usize);
+    position += 8;
+  }
+
+  for (y, &offset) in offsets.iter().enumerate() {
+    assert_eq!(i32_at(data, offset), y as i32);
+    let size = i32_at(data, offset + 4) as usize;
+    let chunk = &data[offset + 8..offset + 8 + size];
+    let line = if size < line_size { decode_rle(chunk) } else { chunk.to_vec() };
+    assert_eq!(line.len(), line_size);
+
+    let mut at = 0;
+    for (plane, channel) in planes.iter_mut().zip(&channels) {
+      for _ in 0..width {
+        if channel.1 == 1 {
+          plane.1.push(half_to_f32(u16::from_le_bytes([line[at], line[at + 1]])));
+          at += 2;
+        } else {
+          plane.1.push(f32::from_le_bytes(line[at..at + 4].try_into().unwrap()));
+          at += 4;
+        }
+      }
+    }
+  }
+
+  planes
+}
+
+fn test_image(width: usize, height: usize) -> Image {
+  let mut image = Image::new(width, height);
+  let mut depth = Layer::new("depth", &["Z"], PixelType::Float, width, height);
+  let mut id = Layer::new("id", &[""], PixelType::Float, width, height);
+  for i in 0..width * height {
+    let (x, y) = ((i % width) as f32, (i / width) as f32);
+    image.pixels[i] = if x < 8.0 { Color::new(0.25, 0.5, 1.0) } else { Color::new(x * 0.1, y * 7.0, 1.0 / (x + 1.0)) };
+    depth.set_pixel(i, &[1.0 + x * 0.001 + y * 1e-6]);
+    id.set_pixel(i, &[if x < 8.0 { -1.0 } else { 3.0 }]);
+  }
+  image.layers = vec![depth, id];
+  image
+}
+
+#[test]
+fn converts_to_half_with_rounding() {
+  assert_eq!(f32_to_half(0.0), 0x0000);
+  assert_eq!(f32_to_half(-0.0), 0x8000);
+  assert_eq!(f32_to_half(1.0), 0x3c00);
+  assert_eq!(f32_to_half(-2.0), 0xc000);
+  assert_eq!(f32_to_half(65504.0), 0x7bff);
+  assert_eq!(f32_to_half(65520.0), 0x7c00);
+  assert_eq!(f32_to_half(1e10), 0x7c00);
+  assert_eq!(f32_to_half(f32::INFINITY), 0x7c00);
+  assert_eq!(f32_to_half(f32::NAN) & 0x7c00, 0x7c00);
+  assert_ne!(f32_to_half(f32::NAN) & 0x3ff, 0);
+  // smallest subnormal, and values rounding to it or to zero
+  assert_eq!(f32_to_half(2f32.powi(-24)), 0x0001);
+  assert_eq!(f32_to_half(2f32.powi(-25) * 1.1), 0x0001);
+  assert_eq!(f32_to_half(2f32.powi(-26)), 0x0000);
+  // ties go to the even mantissa
+  assert_eq!(f32_to_half(1.0 + 2f32.powi(-11)), 0x3c00);
+  assert_eq!(f32_to_half(1.0 + 3.0 * 2f32.powi(-11)), 0x3c02);
+
+  for &value in &[0.1f32, 0.333, 2.71, 1234.5, -7e-5, 6e-8] {
+    let error = (half_to_f32(f32_to_half(value)) - value).abs();
+    assert!(error <= value.abs() * 2f32.powi(-11) + 2f32.powi(-25), "{} came back {} off", value, error);
+  }
+}
+
+#[test]
+fn header_lists_sorted_channels_and_windows() {
+  let mut data = Vec::new();
+  test_image(5, 3).write_exr(&mut data, Compression::Zip, PixelType::Half).unwrap();
+  let (attributes, _) = header(&data);
+
+  let names: Vec<&str> = attributes.iter().map(|a| a.0.as_str()).collect();
+  for required in &["channels", "compression", "dataWindow", "displayWindow", "lineOrder", "pixelAspectRatio",
+    "screenWindowCenter", "screenWindowWidth"] {
+    assert!(names.contains(required), "missing {}", required);
+  }
+
+  assert_eq!(
+    channel_names(&attributes),
+    vec![
+      ("B".to_string(), 1),
+      ("G".to_string(), 1),
+      ("R".to_string(), 1),
+      ("depth.Z".to_string(), 2),
+      ("id".to_string(), 2),
+    ],
+  );
+
+  let compression = attributes.iter().find(|a| a.0 == "compression").unwrap();
+  assert_eq!(compression.2, vec![3]);
+  let window = &attributes.iter().find(|a| a.0 == "dataWindow").unwrap().2;
+  assert_eq!((i32_at(window, 0), i32_at(window, 4), i32_at(window, 8), i32_at(window, 12)), (0, 0, 4, 2));
+}
+
+#[test]
+fn zip_blocks_cover_sixteen_lines() {
+  let (width, height) = (7, 40);
+  let mut data = Vec::new();
+  test_image(width, height).write_exr(&mut data, Compression::Zip, PixelType::Float).unwrap();
+  let (_, position) = header(&data);
+
+  let blocks = height.div_ceil(16);
+  let mut expected_offset = position + blocks * 8;
+  for (block, y) in (0..height).step_by(16).enumerate() {
+    let at = position + block * 8;
+    let offset = u64::from_le_bytes(data[at..at + 8].try_into().unwrap()) as usize;
+    assert_eq!(offset, expected_offset);
+    assert_eq!(i32_at(&data, offset), y as i32);
+
+    let lines = 16.min(height - y);
+    let size = i32_at(&data, offset + 4) as usize;
+    assert!(size <= lines * width * (3 * 4 + 4 + 4));
+    expected_offset = offset + 8 + size;
+  }
+  assert_eq!(expected_offset, data.len());
+}
+
+#[test]
+fn uncompressed_and_rle_files_read_back() {
+  let (width, height) = (37, 9);
+  let image = test_image(width, height);
+
+  for &compression in &[Compression::None, Compression::Rle] {
+    for &beauty in &[PixelType::Half, PixelType::Float] {
+      let mut data = Vec::new();
+      image.write_exr(&mut data, compression, beauty).unwrap();
+      let planes = decode(&data, width, height);
+
+      for (c, name) in ["R", "G", "B"].iter().enumerate() {
+        let plane = &planes.iter().find(|p| p.0 == *name).unwrap().1;
+        for (i, &value) in plane.iter().enumerate() {
+          let expected = image.pixels[i][c];
+          if beauty == PixelType::Float {
+            assert_eq!(value, expected);
+          } else {
+            assert!((value - expected).abs() <= expected * 1e-3, "{} of pixel {} is {}", name, i, value);
+          }
+        }
+      }
+
+      let depth = &planes.iter().find(|p| p.0 == "depth.Z").unwrap().1;
+      assert_eq!(depth, &image.layer("depth").unwrap().channel(0));
+      let id = &planes.iter().find(|p| p.0 == "id").unwrap().1;
+      assert_eq!(id, &image.layer("id").unwrap().channel(0));
+    }
+  }
+}
+
+#[test]
+fn rle_shrinks_flat_images() {
+  let image = Image::new(64, 64);
+  let mut raw = Vec::new();
+  let mut rle = Vec::new();
+  image.write_exr(&mut raw, Compression::None, PixelType::Float).unwrap();
+  image.write_exr(&mut rle, Compression::Rle, PixelType::Float).unwrap();
+  assert!(rle.len() * 4 < raw.len(), "{} vs {} bytes", rle.len(), raw.len());
+}
+
+#[test]
+fn rejects_mismatched_channels() {
+  let samples = [0.0; 5];
+  let channel = Channel { name: "Y".to_string(), pixel_type: PixelType::Half, samples: &samples };
+  assert!(write_exr(&mut Vec::new(), 2, 2, std::slice::from_ref(&channel), Compression::None).is_err());
+  assert!(write_exr(&mut Vec::new(), 5, 1, &[channel.clone(), channel], Compression::None).is_err());
+}
@@ -0,0 +1,121 @@
+use trace::{render, Camera, Color, DirectionalLight, Filter, Plane, RenderSettings, Scene, Vector3D};
+
+const FILTERS: [Filter; 5] = [Filter::Box, Filter::Tent, Filter::Gaussian, Filter::Mitchell, Filter::Lanczos];
+
+#[test]
+fn filters_peak_at_the_center_and_vanish_at_their_radius() {
+  for &filter in &FILTERS {
+    let peak = filter.evaluate(0.0);
+    assert!(peak > 0.0);
+    for i in 1..100 {
+      let x = filter.radius() * i as f32 / 100.0;
+      assert!(filter.evaluate(x) <= peak, "{:?} at {}", filter, x);
+      if filter != Filter::Box {
+        assert_eq!(filter.evaluate(x), filter.evaluate(-x), "{:?} is not symmetric", filter);
+      }
+    }
+    assert_eq!(filter.evaluate(filter.radius()), 0.0);
+    assert_eq!(filter.evaluate(filter.radius() + 0.3), 0.0);
+    assert_eq!(filter.weight(0.2, 0.1), filter.evaluate(0.2) * filter.evaluate(0.1));
+  }
+
+  // nearly continuous where they reach zero
+  for &filter in &FILTERS[1..] {
+    assert!(filter.evaluate(filter.radius() - 1e-3).abs() < 1e-2, "{:?}", filter);
+  }
+}
+
+#[test]
+fn box_filter_is_half_open() {
+  assert_eq!(Filter::Box.evaluate(-0.5), 1.0);
+  assert_eq!(Filter::Box.evaluate(0.5), 0.0);
+  assert_eq!(Filter::Box.evaluate(0.4999), 1.0);
+}
+
+#[test]
+fn filters_have_their_reference_shapes() {
+  assert_eq!(Filter::Tent.evaluate(0.25), 0.75);
+  // Mitchell-Netravali with B = C = 1/3 is 8/9 at the center and dips
+  // negative past one pixel
+  assert!((Filter::Mitchell.evaluate(0.0) - 8.0 / 9.0).abs() < 1e-6);
+  assert!(Filter::Mitchell.evaluate(1.5) < 0.0);
+  // Lanczos crosses zero at every whole pixel
+  assert!(Filter::Lanczos.evaluate(1.0).abs() < 1e-6 && Filter::Lanczos.evaluate(2.0).abs() < 1e-6);
+  assert!(Filter::Lanczos.evaluate(1.5) < 0.0);
+  assert!(Filter::Gaussian.evaluate(1.0) < Filter::Gaussian.evaluate(0.5));
+}
+
+#[test]
+fn names_round_trip() {
+  for (name, &filter) in Filter::NAMES.iter().zip(&FILTERS) {
+    assert_eq!(Filter::from_name(name), Some(filter));
+  }
+  assert_eq!(Filter::from_name("sinc"), None);
+}
+
+/// A lit wall filling the view, cut off by a black plane seen edge on
+/// partway through a pixel.
+fn scene() -> Scene {
+  let mut scene = Scene::new(Camera::default());
+  scene.add(Plane { point: Vector3D::new(0.0, 0.0, -3.0), normal: Vector3D::new(0.0, 0.0, 1.0) }, Color::WHITE);
+  scene.add(
+    Plane { point: Vector3D::new(0.1, 0.0, 0.0), normal: Vector3D::new(1.0, 0.0, 0.0) },
+    Color::BLACK,
+  );
+  scene.add_light(DirectionalLight {
+    direction: Vector3D::new(0.0, 0.0, -1.0),
+    color: Color::WHITE,
+    intensity: 0.5,
+    angle: 0.0,
+    samples: 1,
+  });
+  scene
+}
+
+#[test]
+fn flat_areas_keep_their_value_under_every_filter() {
+  let mut scene = Scene::new(Camera::default());
+  scene.add(Plane { point: Vector3D::new(0.0, 0.0, -3.0), normal: Vector3D::new(0.0, 0.0, 1.0) }, Color::WHITE);
+  scene.add_light(DirectionalLight {
+    direction: Vector3D::new(0.0, 0.0, -1.0),
+    color: Color::WHITE,
+    intensity: 0.5,
+    angle: 0.0,
+    samples: 1,
+  });
+
+  for &filter in &FILTERS {
+    for &samples in &[1, 3, 16] {
+      let settings = RenderSettings { width: 16, height: 12, samples, filter, ..RenderSettings::default() };
+      for pixel in render(&scene, &settings).pixels {
+        assert!((pixel.r - 0.5).abs() < 1e-5, "{:?} with {} samples gave {}", filter, samples, pixel.r);
+      }
+    }
+  }
+}
+
+#[test]
+fn supersampling_smooths_edges() {
+  let scene = scene();
+  let row = |settings: &RenderSettings| -> Vec<f32> {
+    let image = render(&scene, settings);
+    (0..image.width).map(|x| image.pixel(x, image.height / 2).r).collect()
+  };
+
+  // one sample per pixel only gives fully lit or black pixels
+  let aliased = row(&RenderSettings { width: 32, height: 8, ..RenderSettings::default() });
+  assert!(aliased.iter().all(|&v| v == 0.0 || v == 0.5), "{:?}", aliased);
+
+  for &filter in &FILTERS {
+    let smooth = row(&RenderSettings { width: 32, height: 8, samples: 16, filter, ..RenderSettings::default() });
+    let partial = smooth.iter().filter(|&&v| v > 0.01 && v < 0.49).count();
+    assert!(partial >= 1, "{:?}: {:?}", filter, smooth);
+  }
+
+  // wider filters spread the edge over more pixels
+  let count = |filter| {
+    let smooth = row(&RenderSettings { width: 32, height: 8, samples: 16, filter, ..RenderSettings::default() });
+    smooth.iter().filter(|&&v| v > 0.01 && v < 0.49).count()
+  };
+  assert!(count(Filter::Gaussian) > count(Filter::Box));
+}
@@ -0,0 +1,185 @@
+use std::io::{self, ErrorKind};
+
+use trace::format::hdr::{read_hdr, to_rgbe, write_hdr};
+use trace::format::pfm::{read_pfm, write_pfm};
+use trace::Color;
+
+fn from_rgbe(rgbe: [u8; 4]) -> Color {
+  if rgbe[3] == 0 { return Color::default(); }
+  let scale = 2f32.powi(rgbe[3] as i32 - 128 - 8);
+  Color::new(rgbe[0] as f32 * scale, rgbe[1] as f32 * scale, rgbe[2] as f32 * scale)
+}
+
+/// Reads back the scanlines of an RLE encoded Radiance picture.
+fn decode_hdr(data: &[u8], width: usize, height: usize) -> Vec<[u8; 4]> {
+  let header_end = data.windows(2).position(|w| w == b"\n\n").unwrap() + 2;
+  let resolution_end = header_end + data[header_end..].iter().position(|&b| b == b'\n').unwrap() + 1;
+  assert_eq!(&data[header_end..resolution_end], format!("-Y {} +X {}\n", height, width).as_bytes());
+
+  let mut position = resolution_end;
+  let mut pixels = Vec::new();
+  for _ in 0..height {
+    assert_eq!(&data[position..position + 4], &[2, 2, (width >> 8) as u8, width as u8]);
+    position += 4;
+
+    let mut scanline = vec![[0u8; 4]; width];
+    for channel in 0..4 {
+      let mut x = 0;
+      while x < width {
+        let count = data[position] as usize;
+        position += 1;
+        if count > 128 {
+          for pixel in &mut scanline[x..x + count - 128] {
+            pixel[channel] = data[position];
+          }
+          position += 1;
+          x += count - 128;
+        } else {
+          for pixel in &mut scanline[x..x + count] {
+            pixel[channel] = data[position];
+            position += 1;
+          }
+          x += count;
+        }
+      }
+    }
+    pixels.extend(scanline);
+  }
+
+  assert_eq!(position, data.len());
+  pixels
+}
+
+fn gradient(width: usize, height: usize) -> Vec<Color> {
+  (0..width * height)
+    .map(|i| {
+      let (x, y) = ((i % width) as f32, (i / width) as f32);
+      // flat areas for runs, smooth ramps for literals and values well above 1
+      if x < 20.0 { Color::new(0.5, 0.5, 0.5) } else { Color::new(x * 0.37, y * 3.0, 1000.0 / (x + 1.0)) }
+    })
+    .collect()
+}
+
+#[test]
+fn rgbe_keeps_values_above_one() {
+  for &value in &[0.001f32, 0.5, 1.0, 1.5, 37.0, 12345.0] {
+    let color = Color::new(value, value * 0.5, value * 0.25);
+    let decoded = from_rgbe(to_rgbe(&color));
+    assert!((decoded.r - color.r).abs() <= color.r / 128.0, "{} became {}", color.r, decoded.r);
+    assert!((decoded.b - color.b).abs() <= color.r / 128.0);
+  }
+
+  assert_eq!(to_rgbe(&Color::new(0.0, 0.0, 0.0)), [0, 0, 0, 0]);
+  assert_eq!(to_rgbe(&Color::new(1.0, 0.0, 0.0)), [128, 0, 0, 129]);
+}
+
+#[test]
+fn rgbe_saturates_infinite_values() {
+  assert_eq!(to_rgbe(&Color::new(f32::INFINITY, 1.0, 0.0)), [255, 0, 0, 255]);
+  assert_eq!(to_rgbe(&Color::gray(f32::MAX)), [255, 255, 255, 255]);
+
+  let pixels = vec![Color::new(f32::INFINITY, 0.5, 0.0), Color::gray(2.0)];
+  let mut data = Vec::new();
+  write_hdr(&mut data, 2, 1, &pixels).unwrap();
+  let (_, _, read) = read_hdr(&mut &data[..]).unwrap();
+  assert!(read[0].r > 1e38 && read[0].g < read[0].r / 100.0);
+  assert!((read[1].g - 2.0).abs() < 0.01);
+}
+
+#[test]
+fn hdr_round_trips_through_run_length_encoding() {
+  let (width, height) = (300, 4);
+  let pixels = gradient(width, height);
+  let mut data = Vec::new();
+  write_hdr(&mut data, width, height, &pixels).unwrap();
+
+  assert!(data.starts_with(b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n"));
+  let decoded = decode_hdr(&data, width, height);
+  let expected: Vec<_> = pixels.iter().map(to_rgbe).collect();
+  assert_eq!(decoded, expected);
+  assert!(data.len() < width * height * 4, "runs should make the file smaller");
+}
+
+#[test]
+fn pfm_stores_rows_bottom_up() {
+  let pixels = vec![
+    Color::new(1.0, 2.0, 3.0), Color::new(4.0, 5.0, 6.0),
+    Color::new(7.0, 8.0, 9.0), Color::new(10.0, 11.0, 12.5),
+  ];
+  let mut data = Vec::new();
+  write_pfm(&mut data, 2, 2, &pixels).unwrap();
+
+  let header = b"PF\n2 2\n-1.0\n";
+  assert!(data.starts_with(header));
+  let floats: Vec<f32> = data[header.len()..]
+    .chunks(4)
+    .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    .collect();
+  assert_eq!(floats, vec![7.0, 8.0, 9.0, 10.0, 11.0, 12.5, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+}
+
+#[test]
+fn hdr_reads_back_what_was_written() {
+  // wide enough for run-length encoding and too narrow for it
+  for &(width, height) in &[(300, 4), (5, 3)] {
+    let pixels = gradient(width, height);
+    let mut data = Vec::new();
+    write_hdr(&mut data, width, height, &pixels).unwrap();
+
+    let (read_width, read_height, read) = read_hdr(&mut &data[..]).unwrap();
+    assert_eq!((read_width, read_height), (width, height));
+    for (original, read) in pixels.iter().zip(&read) {
+      let largest = original.r.max(original.g).max(original.b);
+      for c in 0..3 {
+        assert!((read[c] - original[c]).abs() <= largest / 128.0, "{:?} became {:?}", original, read);
+      }
+    }
+  }
+}
+
+#[test]
+fn hdr_rejects_broken_files() {
+  assert!(read_hdr(&mut &b"P6\n1 1\n255\n"[..]).is_err());
+  assert!(read_hdr(&mut &b"#?RADIANCE\nFORMAT=32-bit_rle_xyze\n\n-Y 1 +X 1\n\0\0\0\0"[..]).is_err());
+  assert!(read_hdr(&mut &b"#?RADIANCE\n\n+Y 1 +X 1\n\0\0\0\0"[..]).is_err());
+
+  let mut data = Vec::new();
+  write_hdr(&mut data, 300, 4, &gradient(300, 4)).unwrap();
+  data.truncate(data.len() - 10);
+  assert!(read_hdr(&mut &data[..]).is_err());
+}
+
+fn invalid<T>(result: io::Result<T>) -> bool {
+  matches!(result, Err(err) if err.kind() == ErrorKind::InvalidData)
+}
+
+#[test]
+fn float_readers_check_sizes_before_allocating() {
+  assert!(invalid(read_hdr(&mut &b"#?RADIANCE\n\n-Y 4000000000 +X 4000000000\n\0\0\0\0"[..])));
+  assert!(invalid(read_hdr(&mut &b"#?RADIANCE\n\n-Y 18446744073709551615 +X 2\n\0\0\0\0"[..])));
+  // far more scanlines than even the tightest run-length encoding fits in
+  assert!(invalid(read_hdr(&mut &b"#?RADIANCE\n\n-Y 100000 +X 1000\n\x02\x02\x03\xe8"[..])));
+
+  assert!(invalid(read_pfm(&mut &b"PF\n4000000000 4000000000\n-1.0\n\0\0\0\0"[..])));
+  assert!(invalid(read_pfm(&mut &b"Pf\n18446744073709551615 1\n-1.0\n\0\0\0\0"[..])));
+  assert!(invalid(read_pfm(&mut &b"PF\n1000 1000\n-1.0\n\0\0\0\0"[..])));
+}
+
+#[test]
+fn pfm_reads_back_either_byte_order() {
+  let pixels = gradient(7, 3);
+  let mut data = Vec::new();
+  write_pfm(&mut data, 7, 3, &pixels).unwrap();
+  assert_eq!(read_pfm(&mut &data[..]).unwrap(), (7, 3, pixels));
+
+  // a big-endian greyscale map, bottom row first
+  let mut data = b"Pf\n2 2\n1.0\n".to_vec();
+  for value in &[3.0f32, 4.0, 1.0, 2.0] {
+    data.extend_from_slice(&value.to_be_bytes());
+  }
+  let (_, _, read) = read_pfm(&mut &data[..]).unwrap();
+  assert_eq!(read, vec![Color::gray(1.0), Color::gray(2.0), Color::gray(3.0), Color::gray(4.0)]);
+
+  assert!(read_pfm(&mut &data[..data.len() - 1]).is_err());
+  assert!(read_pfm(&mut &b"P6\n2 2\n1.0\n"[..]).is_err());
+}
@@ -0,0 +1,263 @@
+use trace::random::Rng;
+use trace::{
+  Camera, Color, DirectionalLight, DiscLight, Light, Plane, PointLight, Ray, RectangleLight, RenderSettings, Renderer, Scene,
+  Sphere, SphereLight, SpotLight, Vector3D,
+};
+
+fn assert_close(actual: Color, expected: Color) {
+  assert!((0..3).all(|c| (actual[c] - expected[c]).abs() < 1e-5), "expected {:?}, got {:?}", expected, actual);
+}
+
+/// A white floor at y = 0, with the renderer's irradiance at `point` on it.
+fn floor_irradiance(scene: &Scene, x: f32, z: f32) -> Color {
+  let ray = Ray::new(Vector3D::new(x, 1e-3, z), Vector3D::new(0.0, -1.0, 0.0));
+  let hit = scene.intersect(&ray).expect("the floor should be hit");
+  let settings = RenderSettings::default();
+  Renderer::new(scene, &settings).irradiance(&hit, &mut Rng::new(0))
+}
+
+fn floor() -> Scene {
+  let mut scene = Scene::new(Camera::default());
+  scene.add(Plane { point: Vector3D::default(), normal: Vector3D::new(0.0, 1.0, 0.0) }, Color::WHITE);
+  scene
+}
+
+fn spot(inner_angle: f32, outer_angle: f32) -> SpotLight {
+  SpotLight {
+    position: Vector3D::new(0.0, 2.0, 0.0),
+    direction: Vector3D::new(0.0, -1.0, 0.0),
+    color: Color::WHITE,
+    intensity: 4.0,
+    inner_angle,
+    outer_angle,
+  }
+}
+
+#[test]
+fn point_lights_fall_off_with_the_square_of_the_distance() {
+  let mut scene = floor();
+  scene.add_light(PointLight { position: Vector3D::new(0.0, 2.0, 0.0), color: Color::new(1.0, 0.5, 0.25), intensity: 8.0 });
+
+  // straight below, 2 units away
+  assert_close(floor_irradiance(&scene, 0.0, 0.0), Color::new(2.0, 1.0, 0.5));
+
+  // 2 units to the side the light is sqrt(8) away and 45 degrees off the
+  // normal
+  let expected = 8.0 / 8.0 * std::f32::consts::FRAC_1_SQRT_2;
+  assert_close(floor_irradiance(&scene, 2.0, 0.0), Color::new(1.0, 0.5, 0.25) * expected);
+}
+
+#[test]
+fn point_light_samples_point_at_the_light() {
+  let light = PointLight { position: Vector3D::new(1.0, 2.0, 3.0), color: Color::WHITE, intensity: 1.0 };
+  let sample = light.sample(Vector3D::new(1.0, 2.0, -1.0), (0.5, 0.5)).unwrap();
+  assert!((sample.direction - Vector3D::new(0.0, 0.0, 1.0)).magnitude() < 1e-6);
+  assert_eq!(sample.distance, 4.0);
+  assert!(light.sample(light.position, (0.5, 0.5)).is_none());
+}
+
+#[test]
+fn shadow_rays_stop_at_the_light() {
+  let mut scene = floor();
+  scene.add_light(PointLight { position: Vector3D::new(0.0, 2.0, 0.0), color: Color::WHITE, intensity: 4.0 });
+
+  // a sphere above the light doesn't shadow the floor
+  scene.add(Sphere { position: Vector3D::new(0.0, 4.0, 0.0), radius: 1.0 }, Color::WHITE);
+  assert_close(floor_irradiance(&scene, 0.0, 0.0), Color::WHITE);
+
+  // one between the light and the floor does
+  scene.add(Sphere { position: Vector3D::new(0.0, 1.0, 0.0), radius: 0.5 }, Color::WHITE);
+  assert_close(floor_irradiance(&scene, 0.0, 0.0), Color::BLACK);
+}
+
+#[test]
+fn spot_lights_fade_between_their_cone_angles() {
+  let light = spot(20.0, 40.0);
+  let at = |degrees: f32| light.falloff(Vector3D::new(degrees.to_radians().sin(), -degrees.to_radians().cos(), 0.0));
+
+  assert_eq!(at(0.0), 1.0);
+  assert_eq!(at(19.9), 1.0);
+  assert_eq!(at(40.1), 0.0);
+  assert_eq!(at(90.0), 0.0);
+
+  let mut previous = 1.0;
+  for i in 1..100 {
+    let value = at(20.0 + i as f32 * 0.2);
+    assert!(value < previous && value > 0.0, "falloff should shrink smoothly");
+    previous = value;
+  }
+  // smoothstep is flat at both ends
+  assert!(1.0 - at(20.5) < 0.01 && at(39.5) < 0.01);
+
+  // a hard edge when both angles match
+  let hard = spot(30.0, 30.0);
+  assert_eq!(hard.falloff(Vector3D::new(0.49, -1.0, 0.0)), 1.0);
+  assert_eq!(hard.falloff(Vector3D::new(0.6, -1.0, 0.0)), 0.0);
+}
+
+#[test]
+fn spot_lights_only_light_their_cone() {
+  let mut scene = floor();
+  scene.add_light(spot(20.0, 30.0));
+
+  assert_close(floor_irradiance(&scene, 0.0, 0.0), Color::WHITE);
+  // tan(30) * 2 = 1.15 units from the center the cone ends
+  assert_close(floor_irradiance(&scene, 0.0, 1.2), Color::BLACK);
+  assert_close(floor_irradiance(&scene, -1.2, 0.0), Color::BLACK);
+
+  let edge = floor_irradiance(&scene, 0.9, 0.0);
+  assert!(edge.r > 0.0 && edge.r < 4.0 / (0.81 + 4.0));
+}
+
+#[test]
+fn surfaces_filter_colored_light_per_channel() {
+  let mut scene = Scene::new(Camera::default());
+  scene.add(Plane { point: Vector3D::new(0.0, 0.0, -3.0), normal: Vector3D::new(0.0, 0.0, 1.0) }, Color::new(0.5, 1.0, 0.0));
+  scene.add_light(DirectionalLight {
+    direction: Vector3D::new(0.0, 0.0, -1.0),
+    color: Color::new(1.0, 0.5, 1.0),
+    intensity: 2.0,
+    angle: 0.0,
+    samples: 1,
+  });
+  scene.add_light(PointLight { position: Vector3D::new(0.0, 0.0, -1.0), color: Color::new(0.0, 0.0, 1.0), intensity: 4.0 });
+
+  let settings = RenderSettings::default();
+  let color = Renderer::new(&scene, &settings).trace(&Ray::new(Vector3D::default(), Vector3D::new(0.0, 0.0, -1.0)), &mut Rng::new(0));
+  // the blue light only reaches a surface that reflects no blue
+  assert_close(color, Color::new(1.0, 1.0, 0.0));
+}
+
+fn assert_near(actual: f32, expected: f32, tolerance: f32) {
+  assert!((actual - expected).abs() <= expected * tolerance, "expected {}, got {}", expected, actual);
+}
+
+/// A light panel two units above the floor, facing down.
+fn panel(samples: usize) -> RectangleLight {
+  RectangleLight {
+    position: Vector3D::new(0.0, 2.0, 0.0),
+    edge_u: Vector3D::new(1.0, 0.0, 0.0),
+    edge_v: Vector3D::new(0.0, 0.0, 1.0),
+    color: Color::WHITE,
+    intensity: 3.0,
+    samples,
+  }
+}
+
+#[test]
+fn area_lights_converge_to_their_exact_irradiance() {
+  // a disc of radius r at height h delivers intensity * r² / (r² + h²) on
+  // its axis
+  let mut scene = floor();
+  scene.add_light(DiscLight {
+    center: Vector3D::new(0.0, 1.0, 0.0),
+    normal: Vector3D::new(0.0, -1.0, 0.0),
+    radius: 1.0,
+    color: Color::WHITE,
+    intensity: 2.0,
+    samples: 400,
+  });
+  assert_near(floor_irradiance(&scene, 0.0, 0.0).r, 1.0, 0.01);
+
+  // and a sphere above the horizon intensity * (r / d)² at any distance
+  for &height in &[1.5, 4.0, 100.0] {
+    let mut scene = floor();
+    scene.add_light(SphereLight {
+      position: Vector3D::new(0.0, height, 0.0),
+      radius: 1.0,
+      color: Color::new(1.0, 0.5, 0.0),
+      intensity: 2.0,
+      samples: 64,
+    });
+    let irradiance = floor_irradiance(&scene, 0.0, 0.0);
+    assert_near(irradiance.r, 2.0 / (height * height), 0.01);
+    assert_near(irradiance.g, 1.0 / (height * height), 0.01);
+    assert_eq!(irradiance.b, 0.0);
+  }
+
+  // far away, a small panel is like a point light of intensity * area / π
+  let mut scene = floor();
+  scene.add_light(RectangleLight { position: Vector3D::new(0.0, 50.0, 0.0), ..panel(4) });
+  assert_near(floor_irradiance(&scene, 0.0, 0.0).r, 3.0 / std::f32::consts::PI / 2500.0, 0.001);
+}
+
+#[test]
+fn area_lights_only_shine_from_their_front() {
+  let mut scene = floor();
+  let mut light = panel(16);
+  std::mem::swap(&mut light.edge_u, &mut light.edge_v);
+  scene.add_light(light);
+  assert_close(floor_irradiance(&scene, 0.0, 0.0), Color::BLACK);
+}
+
+#[test]
+fn area_lights_cast_soft_shadows() {
+  let wall = trace::AxisAlignedBox { min: Vector3D::new(-10.0, 0.9, -10.0), max: Vector3D::new(0.0, 1.1, 10.0) };
+  let mut open = floor();
+  open.add_light(panel(256));
+  // the same with a wall halfway up, ending below the middle of the panel
+  let mut shadowed = floor();
+  shadowed.add_light(panel(256));
+  shadowed.add(wall.clone(), Color::WHITE);
+
+  assert_eq!(floor_irradiance(&shadowed, -1.5, 0.0).r, 0.0);
+  assert_close(floor_irradiance(&shadowed, 1.5, 0.0), floor_irradiance(&open, 1.5, 0.0));
+
+  let mut previous = 0.0;
+  for i in 0..5 {
+    let x = -0.3 + i as f32 * 0.15;
+    let visible = floor_irradiance(&shadowed, x, 0.0).r / floor_irradiance(&open, x, 0.0).r;
+    assert!(visible > previous && visible < 1.0, "the shadow should fade in, {} visible at {}", visible, x);
+    previous = visible;
+  }
+
+  // a single sample per point gives a hard shadow instead
+  let mut hard = floor();
+  hard.add_light(panel(1));
+  hard.add(wall, Color::WHITE);
+  for i in 0..5 {
+    let x = -0.3 + i as f32 * 0.15;
+    let irradiance = floor_irradiance(&hard, x, 0.0).r;
+    let unoccluded = panel(1).sample(Vector3D::new(x, 0.0, 0.0), (0.5, 0.5)).unwrap();
+    assert!(irradiance == 0.0 || irradiance > unoccluded.irradiance.r * 0.5);
+  }
+}
+
+#[test]
+fn camera_rays_see_area_lights() {
+  let mut scene = Scene::new(Camera::default());
+  scene.add(Plane { point: Vector3D::new(0.0, 0.0, -10.0), normal: Vector3D::new(0.0, 0.0, 1.0) }, Color::WHITE);
+  scene.add(Sphere { position: Vector3D::new(1.5, 0.0, -3.0), radius: 0.5 }, Color::BLACK);
+  scene.add_light(SphereLight { position: Vector3D::new(0.0, 0.0, -5.0), radius: 1.0, color: Color::new(1.0, 0.5, 0.25), intensity: 4.0, samples: 1 });
+  scene.add_light(RectangleLight {
+    position: Vector3D::new(3.0, 0.0, -6.0),
+    edge_u: Vector3D::new(1.0, 0.0, 0.0),
+    edge_v: Vector3D::new(0.0, 1.0, 0.0),
+    color: Color::WHITE,
+    intensity: 2.0,
+    samples: 1,
+  });
+  scene.add_light(DiscLight {
+    center: Vector3D::new(-3.0, 0.0, -6.0),
+    normal: Vector3D::new(0.0, 0.0, -1.0),
+    radius: 1.0,
+    color: Color::WHITE,
+    intensity: 2.0,
+    samples: 1,
+  });
+
+  let settings = RenderSettings::default();
+  let renderer = Renderer::new(&scene, &settings);
+  let trace = |x: f32| renderer.trace(&Ray::new(Vector3D::default(), Vector3D::new(x, 0.0, -5.0).normalize()), &mut Rng::new(0));
+
+  // the glowing sphere hides the wall behind it
+  assert_close(trace(0.0), Color::new(4.0, 2.0, 1.0));
+  // the panel is behind an ordinary sphere, which hides it
+  assert_close(trace(2.5), Color::BLACK);
+  // the back of the disc faces the camera
+  assert_close(trace(-2.5), Color::BLACK);
+
+  // walking to the other side of the sphere shows the panel
+  let ray = Ray::new(Vector3D::new(3.0, 0.5, 0.0), Vector3D::new(0.0, -0.08, -1.0).normalize());
+  assert_close(renderer.trace(&ray, &mut Rng::new(0)), Color::gray(2.0));
+}
@@ -0,0 +1,93 @@
+use trace::random::Rng;
+use trace::scene_file::parse;
+use trace::{Camera, Color, DirectionalLight, Material, Plane, Ray, RenderSettings, Renderer, Scene, Vector3D};
+
+fn glossy(shininess: f32) -> Material {
+  Material { diffuse: Color::new(0.5, 0.0, 0.0), specular: Color::gray(0.25), shininess }
+}
+
+fn assert_close(actual: Color, expected: Color) {
+  assert!((0..3).all(|c| (actual[c] - expected[c]).abs() < 1e-5), "expected {:?}, got {:?}", expected, actual);
+}
+
+/// The color seen looking down at the origin of a floor along `view`, lit
+/// from straight above.
+fn floor_color(material: Material, view: Vector3D) -> Color {
+  let mut scene = Scene::new(Camera::default());
+  scene.add(Plane { point: Vector3D::default(), normal: Vector3D::new(0.0, 1.0, 0.0) }, material);
+  scene.add_light(DirectionalLight {
+    direction: Vector3D::new(0.0, -1.0, 0.0),
+    color: Color::WHITE,
+    intensity: 2.0,
+    angle: 0.0,
+    samples: 1,
+  });
+
+  let settings = RenderSettings::default();
+  let ray = Ray::new(-view * 3.0, view);
+  Renderer::new(&scene, &settings).trace(&ray, &mut Rng::new(0))
+}
+
+#[test]
+fn plain_colors_are_diffuse() {
+  let material = Material::from(Color::new(0.2, 0.4, 0.6));
+  assert_eq!(material.specular, Color::BLACK);
+
+  let normal = Vector3D::new(0.0, 1.0, 0.0);
+  let up = Vector3D::new(0.0, 1.0, 0.0);
+  assert_eq!(material.reflectance(normal, up, up), material.diffuse);
+  assert_eq!(material.reflectance(normal, Vector3D::new(1.0, 0.2, 0.0), Vector3D::new(-1.0, 1.0, 0.0)), material.diffuse);
+}
+
+#[test]
+fn highlights_peak_in_the_mirror_direction() {
+  let normal = Vector3D::new(0.0, 1.0, 0.0);
+  let to_light = Vector3D::new(-1.0, 1.0, 0.0);
+  let mirror = Vector3D::new(1.0, 1.0, 0.0);
+  let off_mirror = Vector3D::new(1.0, 2.0, 0.0);
+
+  for &shininess in &[8.0, 64.0] {
+    assert_close(glossy(shininess).reflectance(normal, mirror, to_light), Color::new(0.75, 0.25, 0.25));
+  }
+
+  // and fall off faster the shinier the surface
+  let highlight = |shininess| glossy(shininess).reflectance(normal, off_mirror, to_light).g;
+  assert!(highlight(8.0) > highlight(64.0) && highlight(64.0) > 0.0);
+  assert!(highlight(8.0) < 0.25);
+}
+
+#[test]
+fn highlights_follow_the_viewer() {
+  let down = Vector3D::new(0.0, -1.0, 0.0);
+  let slanted = Vector3D::new(1.0, -1.0, 0.0).normalize();
+
+  // the diffuse part looks the same from anywhere
+  let matte = Material::from(Color::new(0.5, 0.0, 0.0));
+  assert_close(floor_color(matte, down), Color::new(1.0, 0.0, 0.0));
+  assert_close(floor_color(matte, slanted), Color::new(1.0, 0.0, 0.0));
+
+  // looking straight down at a floor lit from above catches the highlight
+  assert_close(floor_color(glossy(16.0), down), Color::new(1.5, 0.5, 0.5));
+  let cos_halfway = (22.5f32).to_radians().cos();
+  assert_close(floor_color(glossy(16.0), slanted), Color::new(1.0, 0.0, 0.0) + Color::gray(0.5 * cos_halfway.powf(16.0)));
+}
+
+#[test]
+fn scene_files_define_glossy_materials() {
+  let source = "
+    material glossy { color 0.5 0 0 specular 0.25 0.25 0.25 shininess 16 }
+    material plastic { color 0 0 1 specular 1 1 1 }
+    sphere { position 0 0 -5 radius 1 material glossy }
+    sphere { position 0 0 -9 radius 1 material plastic }
+    sphere { position 0 0 -13 radius 1 color 0 1 0 }
+  ";
+  let scene = parse(source).unwrap().scene;
+  assert_eq!(scene.object(0).material, glossy(16.0));
+  assert_eq!(scene.object(1).material.shininess, 32.0);
+  assert_eq!(scene.object(2).material, Material::from(Color::new(0.0, 1.0, 0.0)));
+
+  let error = |source| parse(source).expect_err("the scene should not parse").to_string();
+  assert!(error("material a { color 1 1 1 shininess -1 }").contains("at least 0"));
+  assert!(error("material a { specular 1 1 1 }").contains("material is missing `color`"));
+  assert!(error("sphere { position 0 0 0 radius 1 specular 1 1 1 }").contains("unknown sphere property `specular`"));
+}
@@ -0,0 +1,177 @@
+use trace::format::deflate::{adler32, deflate, zlib_compress, Compression};
+use trace::format::png::{crc32, write_png, ColorType, Samples};
+
+/// Just enough of an inflater to read back stored and fixed Huffman blocks.
+struct BitReader<'a> {
+  data: &'a [u8],
+  position: usize,
+}
+
+impl BitReader<'_> {
+  fn bit(&mut self) -> u32 {
+    let bit = (self.data[self.position / 8] >> (self.position % 8)) & 1;
+    self.position += 1;
+    bit as u32
+  }
+
+  fn bits(&mut self, count: u32) -> u32 {
+    (0..count).fold(0, |value, i| value | self.bit() << i)
+  }
+
+  /// Reads a Huffman code `length` bits long, most significant bit first.
+  fn code(&mut self, length: u32) -> u32 {
+    (0..length).fold(0, |value, _| value << 1 | self.bit())
+  }
+}
+
+fn fixed_literal(reader: &mut BitReader) -> u32 {
+  let code = reader.code(7);
+  if code <= 0x17 { return code + 256; }
+  let code = code << 1 | reader.bit();
+  if (0x30..=0xbf).contains(&code) { return code - 0x30; }
+  if (0xc0..=0xc7).contains(&code) { return code - 0xc0 + 280; }
+  (code << 1 | reader.bit()) - 0x190 + 144
+}
+
+fn inflate(data: &[u8]) -> Vec<u8> {
+  const LENGTH_BASE: [u32; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+  const LENGTH_EXTRA: [u32; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+  const DISTANCE_BASE: [u32; 30] = [1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577];
+  const DISTANCE_EXTRA: [u32; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+
+  let mut reader = BitReader { data, position: 0 };
+  let mut out = Vec::new();
+
+  loop {
+    let last = reader.bit();
+    match reader.bits(2) {
+      0 => {
+        reader.position = reader.position.div_ceil(8) * 8;
+        let length = reader.bits(16);
+        assert_eq!(reader.bits(16), !length & 0xffff);
+        for _ in 0..length {
+          out.push(reader.bits(8) as u8);
+        }
+      },
+      1 => loop {
+        let symbol = fixed_literal(&mut reader);
+        if symbol < 256 {
+          out.push(symbol as u8);
+        } else if symbol == 256 {
+          break;
+        } else {
+          let l = (symbol - 257) as usize;
+          let length = LENGTH_BASE[l] + reader.bits(LENGTH_EXTRA[l]);
+          let d = reader.code(5) as usize;
+          let distance = (DISTANCE_BASE[d] + reader.bits(DISTANCE_EXTRA[d])) as usize;
+          for _ in 0..length {
+            out.push(out[out.len() - distance]);
+          }
+        }
+      },
+      kind => panic!("unexpected block type {}", kind),
+    }
+    if last == 1 { return out; }
+  }
+}
+
+fn sample_data() -> Vec<u8> {
+  let mut data = Vec::new();
+  let mut state = 12345u32;
+  for i in 0..200_000u32 {
+    // a mix of repetitive runs and noise so both block types get used
+    state = state.wrapping_mul(1103515245).wrapping_add(12345);
+    data.push(if (i / 5000) % 3 == 0 { (state >> 16) as u8 } else { (i % 251) as u8 });
+  }
+  data
+}
+
+#[test]
+fn checksums_match_reference_values() {
+  assert_eq!(crc32(b"123456789"), 0xcbf43926);
+  assert_eq!(crc32(b""), 0);
+  assert_eq!(adler32(b"Wikipedia"), 0x11e60398);
+  assert_eq!(adler32(b""), 1);
+}
+
+#[test]
+fn deflate_round_trips() {
+  let data = sample_data();
+  for &compression in &[Compression::Stored, Compression::Fixed] {
+    assert_eq!(inflate(&deflate(&data, compression)), data);
+    assert_eq!(inflate(&deflate(&[], compression)), Vec::<u8>::new());
+    assert_eq!(inflate(&deflate(b"a", compression)), b"a".to_vec());
+  }
+
+  let repetitive = vec![7u8; 100_000];
+  let compressed = deflate(&repetitive, Compression::Fixed);
+  assert!(compressed.len() < 1000, "{} bytes", compressed.len());
+  assert_eq!(inflate(&compressed), repetitive);
+}
+
+#[test]
+fn zlib_streams_are_framed() {
+  let data = sample_data();
+  let stream = zlib_compress(&data, Compression::Fixed);
+
+  assert_eq!((stream[0] as u16 * 256 + stream[1] as u16) % 31, 0);
+  assert_eq!(&stream[stream.len() - 4..], &adler32(&data).to_be_bytes());
+  assert_eq!(inflate(&stream[2..stream.len() - 4]), data);
+}
+
+/// Splits a PNG into its chunks, checking the signature and every CRC.
+fn chunks(png: &[u8]) -> Vec<([u8; 4], Vec<u8>)> {
+  assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+  let mut chunks = Vec::new();
+  let mut position = 8;
+  while position < png.len() {
+    let length = u32::from_be_bytes([png[position], png[position + 1], png[position + 2], png[position + 3]]) as usize;
+    let kind_and_data = &png[position + 4..position + 8 + length];
+    let crc = &png[position + 8 + length..position + 12 + length];
+    assert_eq!(crc, &crc32(kind_and_data).to_be_bytes());
+
+    let mut kind = [0; 4];
+    kind.copy_from_slice(&kind_and_data[..4]);
+    chunks.push((kind, kind_and_data[4..].to_vec()));
+    position += 12 + length;
+  }
+  chunks
+}
+
+#[test]
+fn writes_sixteen_bit_rgba() {
+  let samples: Vec<u16> = (0..3 * 2 * 4).map(|i| i * 2500).collect();
+  let mut png = Vec::new();
+  write_png(&mut png, 3, 2, ColorType::Rgba, Samples::Sixteen(&samples), Compression::Fixed).unwrap();
+
+  let chunks = chunks(&png);
+  let kinds: Vec<_> = chunks.iter().map(|(kind, _)| kind).collect();
+  assert_eq!(kinds, vec![b"IHDR", b"IDAT", b"IEND"]);
+  assert_eq!(chunks[0].1, vec![0, 0, 0, 3, 0, 0, 0, 2, 16, 6, 0, 0, 0]);
+
+  // every row starts with its filter type and holds 3 pixels of 8 bytes; no
+  // filter changes the first pixel of the first row
+  let raw = inflate(&chunks[1].1[2..chunks[1].1.len() - 4]);
+  assert_eq!(raw.len(), 2 * (1 + 3 * 8));
+  assert!(raw[0] <= 4);
+  assert_eq!(&raw[1..5], &[0, 0, 2500u16.to_be_bytes()[0], 2500u16.to_be_bytes()[1]]);
+}
+
+#[test]
+fn writes_eight_bit_rgb() {
+  let samples: Vec<u8> = (0..16 * 16 * 3).map(|i| (i % 256) as u8).collect();
+  let mut png = Vec::new();
+  write_png(&mut png, 16, 16, ColorType::Rgb, Samples::Eight(&samples), Compression::Stored).unwrap();
+
+  let chunks = chunks(&png);
+  assert_eq!(chunks[0].1[8..10], [8, 2]);
+  let raw = inflate(&chunks[1].1[2..chunks[1].1.len() - 4]);
+  assert_eq!(raw.len(), 16 * (1 + 16 * 3));
+}
+
+#[test]
+fn rejects_mismatched_sample_counts() {
+  let mut png = Vec::new();
+  let result = write_png(&mut png, 4, 4, ColorType::Rgb, Samples::Eight(&[0; 10]), Compression::Fixed);
+  assert!(result.is_err());
+}
@@ -0,0 +1,108 @@
+use trace::{AxisAlignedBox, BoundingBox, Camera, Color, Disc, Plane, Ray, Scene, Shape, Sphere, Vector3D};
+
+fn down_from(x: f32, z: f32) -> Ray {
+  Ray::new(Vector3D::new(x, 5.0, z), Vector3D::new(0.0, -1.0, 0.0))
+}
+
+#[test]
+fn plane_is_hit_from_either_side() {
+  let ground = Plane { point: Vector3D::new(0.0, -1.0, 0.0), normal: Vector3D::new(0.0, 1.0, 0.0) };
+
+  assert_eq!(ground.intersects(&down_from(100.0, -3.0), 0.0, f32::INFINITY), Some(6.0));
+
+  let up = Ray::new(Vector3D::new(0.0, -3.0, 0.0), Vector3D::new(0.0, 1.0, 0.0));
+  assert_eq!(ground.intersects(&up, 0.0, f32::INFINITY), Some(2.0));
+  assert_eq!(ground.surface_normal(&Vector3D::new(1.0, -1.0, 2.0)), Vector3D::new(0.0, 1.0, 0.0));
+}
+
+#[test]
+fn parallel_rays_miss_planes() {
+  let ground = Plane { point: Vector3D::new(0.0, -1.0, 0.0), normal: Vector3D::new(0.0, 1.0, 0.0) };
+  let ray = Ray::new(Vector3D::new(0.0, 0.0, 0.0), Vector3D::new(0.0, 0.0, -1.0));
+
+  assert_eq!(ground.intersects(&ray, 0.0, f32::INFINITY), None);
+}
+
+#[test]
+fn disc_is_bounded_by_its_radius() {
+  let disc = Disc {
+    center: Vector3D::new(0.0, 0.0, 0.0),
+    normal: Vector3D::new(0.0, 1.0, 0.0),
+    radius: 1.0,
+  };
+
+  assert_eq!(disc.intersects(&down_from(0.5, 0.5), 0.0, f32::INFINITY), Some(5.0));
+  assert_eq!(disc.intersects(&down_from(0.8, 0.8), 0.0, f32::INFINITY), None);
+  assert_eq!(disc.intersects(&down_from(0.0, 0.0), 0.0, 4.0), None);
+}
+
+#[test]
+fn box_reports_entry_and_exit() {
+  let cube = AxisAlignedBox { min: Vector3D::new(-1.0, -1.0, -1.0), max: Vector3D::new(1.0, 1.0, 1.0) };
+
+  assert_eq!(cube.intersections(&down_from(0.0, 0.0)), Some((4.0, 6.0)));
+  assert_eq!(cube.intersects(&down_from(0.0, 0.0), 0.0, f32::INFINITY), Some(4.0));
+  assert_eq!(cube.intersects(&down_from(2.0, 0.0), 0.0, f32::INFINITY), None);
+
+  let inside = Ray::new(Vector3D::new(0.0, 0.0, 0.0), Vector3D::new(0.0, 0.0, -1.0));
+  assert_eq!(cube.intersects(&inside, 0.0, f32::INFINITY), Some(1.0));
+}
+
+#[test]
+fn box_normals_point_out_of_the_nearest_face() {
+  let cube = AxisAlignedBox { min: Vector3D::new(-1.0, -1.0, -1.0), max: Vector3D::new(1.0, 1.0, 1.0) };
+
+  assert_eq!(cube.surface_normal(&Vector3D::new(0.2, 1.0, -0.3)), Vector3D::new(0.0, 1.0, 0.0));
+  assert_eq!(cube.surface_normal(&Vector3D::new(-1.0, 0.5, 0.5)), Vector3D::new(-1.0, 0.0, 0.0));
+  assert_eq!(cube.surface_normal(&Vector3D::new(0.1, -0.2, 1.0)), Vector3D::new(0.0, 0.0, 1.0));
+}
+
+#[test]
+fn bounding_boxes_enclose_their_shapes() {
+  let sphere = Sphere { position: Vector3D::new(1.0, 2.0, 3.0), radius: 0.5 };
+  assert_eq!(sphere.bounding_box(), BoundingBox::new(Vector3D::new(0.5, 1.5, 2.5), Vector3D::new(1.5, 2.5, 3.5)));
+
+  let disc = Disc { center: Vector3D::new(0.0, 0.0, 0.0), normal: Vector3D::new(0.0, 1.0, 0.0), radius: 2.0 };
+  assert_eq!(disc.bounding_box(), BoundingBox::new(Vector3D::new(-2.0, 0.0, -2.0), Vector3D::new(2.0, 0.0, 2.0)));
+
+  let ground = Plane { point: Vector3D::new(0.0, 0.0, 0.0), normal: Vector3D::new(0.0, 1.0, 0.0) };
+  assert!(!ground.bounding_box().is_finite());
+}
+
+/// A shape defined outside the crate: the plane z = -2 facing the camera.
+#[derive(Debug)]
+struct Wall;
+
+impl Shape for Wall {
+  fn intersect(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<f32> {
+    let t = (-2.0 - ray.origin.z) / ray.direction.z;
+    if t > t_min && t < t_max { Some(t) } else { None }
+  }
+
+  fn normal(&self, _point: &Vector3D) -> Vector3D {
+    Vector3D::new(0.0, 0.0, 1.0)
+  }
+
+  fn bounding_box(&self) -> BoundingBox {
+    BoundingBox::infinite()
+  }
+
+  fn uv(&self, point: &Vector3D) -> Option<(f32, f32)> {
+    Some((point.x, point.y))
+  }
+}
+
+#[test]
+fn custom_shapes_plug_into_the_scene() {
+  let mut scene = Scene::new(Camera::default());
+  let sphere = scene.add(Sphere { position: Vector3D::new(0.0, 0.0, -5.0), radius: 1.0 }, Color::new(1.0, 0.0, 0.0));
+  let wall = scene.add(Wall, Color::new(0.0, 1.0, 0.0));
+
+  let hit = scene.intersect(&Ray::new(Vector3D::new(0.0, 0.0, 0.0), Vector3D::new(0.0, 0.0, -1.0))).unwrap();
+  assert_eq!(hit.object, wall);
+  assert_eq!(hit.distance, 2.0);
+  assert_eq!(hit.uv, Some((0.0, 0.0)));
+
+  let behind_wall = Ray::new(Vector3D::new(0.0, 0.0, -3.0), Vector3D::new(0.0, 0.0, -1.0));
+  assert_eq!(scene.intersect(&behind_wall).unwrap().object, sphere);
+}
@@ -0,0 +1,170 @@
+use trace::scene_file::parse;
+use trace::{Color, ParseError, Projection, Ray, Vector3D};
+
+fn error(source: &str) -> ParseError {
+  parse(source).expect_err("the scene should not parse")
+}
+
+fn assert_error(source: &str, line: usize, column: usize, message: &str) {
+  let err = error(source);
+  assert_eq!((err.line, err.column), (line, column), "{}", err);
+  assert!(err.message.contains(message), "unexpected message: {}", err.message);
+}
+
+#[test]
+fn parses_the_bundled_scene() {
+  let description = parse(include_str!("../scenes/spheres.txt")).unwrap();
+
+  assert_eq!((description.settings.width, description.settings.height), (800, 600));
+  assert_eq!(description.scene.objects().len(), 5);
+  assert_eq!(description.scene.lights.len(), 2);
+  assert_eq!(description.scene.object(0).material.diffuse, Color::new(1.0, 0.0, 0.0));
+}
+
+#[test]
+fn parses_every_kind_of_block() {
+  let source = "
+    # comments run to the end of the line
+    settings { width 64 height 48 threads 2 tile_size 8 } # and may follow blocks
+    camera { position 0 1 0 look_at 0 1 -1 up 0 2 0 fov 45 }
+    material grey { color 0.5 0.5 0.5 }
+    sphere { position 0 0 -5 radius 1 material grey }
+    plane { point 0 -1 0 normal 0 1 0 color 1 1 1 }
+    disc { center 0 0 -9 normal 0 0 1 radius 2 color 1 0 0 }
+    box { min -1 -1 -8 max 1 1 -7 material grey }
+    mesh {
+      vertex 0 0 -3 vertex 1 0 -3 vertex 0 1 -3 vertex 1 1 -3
+      uv 0 0 uv 1 0 uv 0 1 uv 1 1
+      face 0 1 2
+      face 1 3 2
+      color 0 1 0
+    }
+    light{direction 0 -1 -1 color 1 0.5 0 intensity 0.5}
+    point_light { position 0 2 0 color 1 0 0 intensity 8 }
+    spot_light { position 0 5 0 direction 0 -1 0 intensity 2 inner_angle 10 outer_angle 20 }
+  ";
+  let description = parse(source).unwrap();
+  let scene = &description.scene;
+
+  assert_eq!(description.settings.threads, 2);
+  assert_eq!(description.settings.tile_size, 8);
+  assert_eq!(scene.camera.position, Vector3D::new(0.0, 1.0, 0.0));
+  assert_eq!(scene.camera.target, Vector3D::new(0.0, 1.0, -1.0));
+  assert_eq!(scene.camera.up, Vector3D::new(0.0, 2.0, 0.0));
+  assert_eq!(scene.camera.fov, 45.0);
+  assert_eq!(scene.camera.aperture, 0.0);
+  assert_eq!(scene.camera.focus_distance, 1.0);
+  assert_eq!(scene.objects().len(), 6);
+  assert_eq!(scene.lights.len(), 3);
+  let origin = Vector3D::default();
+  assert_eq!(scene.lights[0].sample(origin, (0.5, 0.5)).unwrap().irradiance, Color::new(0.5, 0.25, 0.0));
+  assert_eq!(scene.lights[1].sample(origin, (0.5, 0.5)).unwrap().irradiance, Color::new(2.0, 0.0, 0.0));
+  assert_eq!(scene.lights[2].sample(origin, (0.5, 0.5)).unwrap().distance, 5.0);
+  assert!(scene.lights[2].sample(Vector3D::new(5.0, 0.0, 0.0), (0.5, 0.5)).is_none());
+
+  let hit = scene.intersect(&Ray::new(Vector3D::new(0.25, 0.25, 0.0), Vector3D::new(0.0, 0.0, -1.0))).unwrap();
+  assert_eq!(hit.distance, 3.0);
+  assert_eq!(hit.object, 4);
+  assert!(hit.uv.is_some());
+}
+
+#[test]
+fn parses_lens_settings() {
+  let camera = parse("camera { position 0 0 4 look_at 0 0 1 aperture 0.1 blades 6 blade_rotation 30 }").unwrap().scene.camera;
+  assert_eq!((camera.aperture, camera.blades, camera.blade_rotation), (0.1, 6, 30.0));
+  assert_eq!(camera.focus_distance, 3.0);
+
+  let camera = parse("camera { look_at 0 0 1 focus_distance 7.5 aperture 1 }").unwrap().scene.camera;
+  assert_eq!(camera.focus_distance, 7.5);
+}
+
+#[test]
+fn parses_area_lights() {
+  let source = "
+    rect_light { position 0 2 0 edge_u 1 0 0 edge_v 0 0 1 intensity 2 samples 4 }
+    disc_light { center 0 2 0 normal 0 -1 0 radius 0.5 color 1 0 0 intensity 1 }
+    sphere_light { position 0 2 0 radius 0.5 intensity 3 }
+  ";
+  let scene = parse(source).unwrap().scene;
+
+  assert_eq!(scene.lights.iter().map(|light| light.samples()).collect::<Vec<_>>(), vec![4, 16, 16]);
+  let down = Ray::new(Vector3D::default(), Vector3D::new(0.0, 1.0, 0.0));
+  assert_eq!(scene.lights[0].emission(&down), Some((2.0, Color::gray(2.0))));
+  assert_eq!(scene.lights[1].emission(&down), Some((2.0, Color::new(1.0, 0.0, 0.0))));
+  assert_eq!(scene.lights[2].emission(&down), Some((1.5, Color::gray(3.0))));
+}
+
+#[test]
+fn parses_projections() {
+  let camera = parse("camera { fov 200 projection fisheye }").unwrap().scene.camera;
+  assert_eq!((camera.projection, camera.fov), (Projection::Fisheye, 200.0));
+
+  let camera = parse("camera { view_height 5 projection orthographic }").unwrap().scene.camera;
+  assert_eq!(camera.projection, Projection::Orthographic { height: 5.0 });
+
+  let camera = parse("camera { projection equirectangular }").unwrap().scene.camera;
+  assert_eq!(camera.projection, Projection::Equirectangular);
+}
+
+#[test]
+fn reports_bad_numbers_where_they_are() {
+  assert_error("sphere {\n  position 0 0 x\n  radius 1\n}", 2, 16, "expected a number, found `x`");
+  assert_error("settings { width -3 }", 1, 18, "expected a whole number");
+  assert_error("settings { width 0 }", 1, 18, "from 1 to 65536");
+  assert_error("settings { width 64 height 5000000000 }", 1, 28, "from 1 to 65536");
+  assert_error("camera { fov 180 }", 1, 14, "below 180 degrees");
+  assert_error("camera { fov 400 projection fisheye }", 1, 14, "between 0 and 360");
+  assert_error("camera { projection cylindrical }", 1, 21, "found `cylindrical`");
+  assert_error("camera { aperture -0.1 }", 1, 19, "at least 0");
+  assert_error("camera { focus_distance 0 }", 1, 25, "greater than zero");
+  assert_error("camera { blades 2 }", 1, 17, "3 to 64 blades");
+  assert_error("light { direction 0 -1 0 intensity -1 }", 1, 36, "at least 0");
+  assert_error("point_light { position 0 1 0 intensity -2 }", 1, 40, "at least 0");
+  assert_error("spot_light { intensity -0.5 }", 1, 24, "at least 0");
+  assert_error("material red { color 1 -0.5 0 }", 1, 24, "at least 0");
+  assert_error("sphere { position 0 0 0 radius -1 color 1 1 1 }", 1, 32, "greater than zero");
+  assert_error("disc { center 0 0 0 normal 0 1 0 radius 0 color 1 1 1 }", 1, 41, "greater than zero");
+}
+
+#[test]
+fn reports_unknown_blocks_and_properties() {
+  assert_error("\n\n  cone { }", 3, 3, "found `cone`");
+  assert_error("light {\n\tdirection 0 0 -1\n\tcolour 1 1 1\n}", 3, 2, "unknown light property `colour`");
+}
+
+#[test]
+fn reports_missing_properties_at_the_block() {
+  assert_error("color 1 1 1\n", 1, 1, "found `color`");
+  assert_error("  sphere { position 0 0 0 color 1 1 1 }", 1, 3, "sphere is missing `radius`");
+  assert_error("light { direction 0 0 -1 }", 1, 1, "light is missing `intensity`");
+  assert_error("point_light { intensity 1 }", 1, 1, "point_light is missing `position`");
+  assert_error("spot_light { position 0 0 0 direction 0 -1 0 intensity 1 }", 1, 1, "missing `outer_angle`");
+  assert_error(
+    "spot_light { position 0 0 0 direction 0 -1 0 intensity 1 inner_angle 30 outer_angle 20 }",
+    1, 73, "must be at least `inner_angle`",
+  );
+  assert_error("disc_light { center 0 0 0 normal 0 -1 0 radius 1 }", 1, 1, "disc_light is missing `intensity`");
+  assert_error("rect_light { position 0 0 0 edge_u 1 0 0 edge_v 2 0 0 intensity 1 }", 1, 42, "must not be parallel");
+  assert_error("sphere_light { position 0 0 0 radius 1 intensity 1 samples 0 }", 1, 60, "greater than zero");
+}
+
+#[test]
+fn reports_undefined_and_duplicate_materials() {
+  assert_error("sphere { position 0 0 0 radius 1 material chrome }", 1, 43, "material `chrome` has not been defined");
+  assert_error("material a { color 1 1 1 }\nmaterial a { color 0 0 0 }", 2, 10, "already defined");
+}
+
+#[test]
+fn reports_unterminated_blocks_at_the_end() {
+  assert_error("sphere {\n  radius 1", 2, 11, "expected `}`, found the end of the file");
+  assert_error("sphere", 1, 7, "expected `{`");
+  assert_error("camera { fov", 1, 13, "expected a number, found the end of the file");
+}
+
+#[test]
+fn validates_meshes() {
+  assert_error("mesh {\n  vertex 0 0 0 vertex 1 0 0 vertex 0 1 0\n  face 0 1 3\n  color 1 1 1\n}", 3, 12, "vertex 3 does not exist");
+  assert_error("mesh { vertex 0 0 0 vertex 1 0 0 vertex 0 1 0 normal 0 0 1 face 0 1 2 color 1 1 1 }", 1, 47, "1 normals but 3 vertices");
+  assert_error("light { direction 0 0 0 intensity 1 }", 1, 19, "must not be zero");
+  assert_error("camera { position 1 2 3 look_at 1 2 3 }", 1, 1, "must differ from its `position`");
+}
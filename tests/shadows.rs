@@ -0,0 +1,96 @@
+use trace::random::Rng;
+use trace::{Camera, Color, DirectionalLight, Ray, RenderSettings, Renderer, Scene, Sphere, Vector3D};
+
+fn sphere(x: f32, y: f32, z: f32, radius: f32) -> Sphere {
+  Sphere { position: Vector3D::new(x, y, z), radius }
+}
+
+fn light(x: f32, y: f32, z: f32, intensity: f32) -> DirectionalLight {
+  DirectionalLight { direction: Vector3D::new(x, y, z), color: Color::WHITE, intensity, angle: 0.0, samples: 1 }
+}
+
+/// Irradiance at the nearest hit straight down the camera's view axis.
+fn irradiance(scene: &Scene) -> Color {
+  let settings = RenderSettings::default();
+  let renderer = Renderer::new(scene, &settings);
+  let ray = scene.camera.ray(0.5, 0.5, 1.0).unwrap();
+  let hit = scene.intersect(&ray).expect("the center ray should hit something");
+  renderer.irradiance(&hit, &mut Rng::new(0))
+}
+
+/// Checks every channel of white light.
+fn assert_close(actual: Color, expected: f32) {
+  assert!((0..3).all(|c| (actual[c] - expected).abs() < 1e-5), "expected {}, got {:?}", expected, actual);
+}
+
+#[test]
+fn unoccluded_light_contributes_once() {
+  let mut scene = Scene::new(Camera::default());
+  scene.add(sphere(0.0, 0.0, -5.0, 1.0), Color::WHITE);
+  scene.add_light(light(0.0, 0.0, -1.0, 0.5));
+
+  assert_close(irradiance(&scene), 0.5);
+}
+
+#[test]
+fn irradiance_does_not_depend_on_scene_size() {
+  let mut scene = Scene::new(Camera::default());
+  scene.add(sphere(0.0, 0.0, -5.0, 1.0), Color::WHITE);
+  scene.add_light(light(0.0, 0.0, -1.0, 0.5));
+
+  for i in 0..10 {
+    scene.add(sphere(10.0 + i as f32 * 3.0, 0.0, -5.0, 1.0), Color::WHITE);
+  }
+
+  assert_close(irradiance(&scene), 0.5);
+}
+
+#[test]
+fn incidence_angle_scales_irradiance() {
+  let mut scene = Scene::new(Camera::default());
+  scene.add(sphere(0.0, 0.0, -5.0, 1.0), Color::WHITE);
+  scene.add_light(light(0.0, -1.0, -1.0, 1.0));
+
+  assert_close(irradiance(&scene), std::f32::consts::FRAC_1_SQRT_2);
+}
+
+#[test]
+fn blocked_light_is_fully_shadowed() {
+  let mut scene = Scene::new(Camera::default());
+  scene.add(sphere(0.0, 0.0, -5.0, 1.0), Color::WHITE);
+  scene.add(sphere(0.0, 0.0, -2.0, 0.1), Color::WHITE);
+  scene.add_light(light(0.0, 0.0, -1.0, 0.5));
+  scene.add_light(light(0.0, -1.0, -1.0, 1.0));
+
+  // start past the small sphere so the large one is hit
+  let ray = Ray::new(Vector3D::new(0.0, 0.0, -3.0), Vector3D::new(0.0, 0.0, -1.0));
+  let hit = scene.intersect(&ray).unwrap();
+  assert_eq!(hit.object, 0);
+
+  // the head-on light is blocked by the small sphere, the angled one is not
+  let settings = RenderSettings::default();
+  let renderer = Renderer::new(&scene, &settings);
+  assert_close(renderer.irradiance(&hit, &mut Rng::new(0)), std::f32::consts::FRAC_1_SQRT_2);
+}
+
+#[test]
+fn lights_behind_the_surface_contribute_nothing() {
+  let mut scene = Scene::new(Camera::default());
+  scene.add(sphere(0.0, 0.0, -5.0, 1.0), Color::WHITE);
+  scene.add_light(light(0.0, 0.0, 1.0, 1.0));
+
+  assert_close(irradiance(&scene), 0.0);
+}
+
+#[test]
+fn grazing_shadow_rays_do_not_hit_their_own_surface() {
+  let mut scene = Scene::new(Camera::default());
+  scene.add(sphere(0.0, 0.0, -5.0, 1.0), Color::WHITE);
+
+  // nearly tangent to the surface at the hit point
+  let direction = Vector3D::new(0.0, -1.0, -0.01);
+  scene.add_light(light(direction.x, direction.y, direction.z, 1.0));
+
+  let cos_theta = 0.01 / direction.magnitude();
+  assert_close(irradiance(&scene), cos_theta);
+}
@@ -0,0 +1,160 @@
+use std::f32::consts::PI;
+
+use trace::random::Rng;
+use trace::scene_file::parse;
+use trace::{Camera, Color, DirectionalLight, Light, Plane, Ray, RenderSettings, Renderer, Scene, Sky, SkyLight, Sphere, Vector3D};
+
+fn sky(elevation: f32) -> Sky {
+  Sky { turbidity: 3.0, elevation, azimuth: 30.0, intensity: 1.0 }
+}
+
+fn assert_direction(actual: Vector3D, expected: Vector3D) {
+  assert!((actual - expected).magnitude() < 1e-6, "expected {:?}, got {:?}", expected, actual);
+}
+
+/// Irradiance on a white floor at `x` along the x axis, lit by `light`
+/// with a ball of radius 1 floating 3 units above the origin if `blocked`.
+fn floor_irradiance(light: impl Light + 'static, blocked: bool, x: f32) -> Color {
+  let mut scene = Scene::new(Camera::default());
+  scene.add(Plane { point: Vector3D::default(), normal: Vector3D::new(0.0, 1.0, 0.0) }, Color::WHITE);
+  if blocked {
+    scene.add(Sphere { position: Vector3D::new(0.0, 3.0, 0.0), radius: 1.0 }, Color::WHITE);
+  }
+  scene.add_light(light);
+
+  let hit = scene.intersect(&Ray::new(Vector3D::new(x, 1e-3, 0.0), Vector3D::new(0.0, -1.0, 0.0))).unwrap();
+  let settings = RenderSettings::default();
+  Renderer::new(&scene, &settings).irradiance(&hit, &mut Rng::new(3))
+}
+
+#[test]
+fn the_sun_sits_at_its_elevation_and_azimuth() {
+  let sun = |elevation, azimuth| Sky { elevation, azimuth, ..sky(0.0) }.sun_direction();
+  assert_direction(sun(90.0, 0.0), Vector3D::new(0.0, 1.0, 0.0));
+  assert_direction(sun(0.0, 0.0), Vector3D::new(0.0, 0.0, -1.0));
+  assert_direction(sun(0.0, 90.0), Vector3D::new(1.0, 0.0, 0.0));
+
+  let light = sky(45.0).sun(4);
+  assert_direction(light.direction, -sky(45.0).sun_direction());
+  assert_eq!(light.samples(), 4);
+}
+
+#[test]
+fn the_sky_is_blue_overhead_and_brightest_around_the_sun() {
+  let sky = sky(40.0);
+  let zenith = sky.radiance(Vector3D::new(0.0, 1.0, 0.0));
+  assert!(zenith.b > zenith.g && zenith.g > zenith.r, "{:?}", zenith);
+
+  let sun = sky.sun_direction();
+  let near_sun = sky.radiance(sun + Vector3D::new(0.0, 0.1, 0.0));
+  let away_from_sun = sky.radiance(Vector3D::new(-sun.x, sun.y + 0.1, -sun.z));
+  assert!(near_sun.luminance() > 2.0 * away_from_sun.luminance());
+
+  assert_eq!(sky.radiance(Vector3D::new(0.3, -0.01, 1.0)), Color::BLACK);
+}
+
+#[test]
+fn low_and_hazy_suns_are_dimmer_and_redder() {
+  let high = sky(60.0).sun(1).color;
+  let low = sky(5.0).sun(1).color;
+  let hazy = Sky { turbidity: 8.0, ..sky(60.0) }.sun(1).color;
+
+  assert!(high.r < 1.0 && high.b < high.r);
+  assert!(low.luminance() < high.luminance() && hazy.luminance() < high.luminance());
+  assert!(low.b / low.r < high.b / high.r);
+}
+
+#[test]
+fn the_sun_shows_as_a_disc_of_its_angle() {
+  let sky = sky(30.0);
+  let sun = sky.sun(1);
+  let towards = sky.sun_direction();
+  assert!(sun.environment(towards).luminance() > 1000.0 * sky.radiance(towards).luminance());
+
+  // 0.2 degrees off is still within the disc, 0.4 degrees is not
+  let beside = |degrees: f32| towards + Vector3D::new(0.0, 0.0, 1.0).cross(&towards).normalize() * degrees.to_radians();
+  assert_ne!(sun.environment(beside(0.2)), Color::BLACK);
+  assert_eq!(sun.environment(beside(0.4)), Color::BLACK);
+
+  assert_eq!(DirectionalLight { angle: 0.0, ..sun }.environment(towards), Color::BLACK);
+}
+
+#[test]
+fn wide_suns_cast_soft_shadows() {
+  let light = |angle| DirectionalLight {
+    direction: Vector3D::new(0.0, -1.0, 0.0),
+    color: Color::WHITE,
+    intensity: 1.0,
+    angle,
+    samples: 64,
+  };
+
+  // right below the edge of the ball, half the disc is hidden
+  let edge = floor_irradiance(light(20.0), true, 1.0).g;
+  assert!(edge > 0.3 && edge < 0.7, "{}", edge);
+  assert_eq!(floor_irradiance(light(0.0), true, 0.99).g, 0.0);
+  // out in the open the disc is all there, slightly off the normal
+  let open = floor_irradiance(light(20.0), true, 2.0).g;
+  assert!((open - (1.0 + 10f32.to_radians().cos()) / 2.0).abs() < 1e-4, "{}", open);
+}
+
+#[test]
+fn sky_light_matches_the_integrated_sky() {
+  let sky = sky(35.0);
+
+  // irradiance on the floor is the sky's radiance integrated over the
+  // hemisphere, weighted by cos θ
+  let steps = 256;
+  let mut expected = Color::BLACK;
+  for i in 0..steps {
+    for j in 0..steps {
+      let theta = (i as f32 + 0.5) / steps as f32 * PI / 2.0;
+      let phi = (j as f32 + 0.5) / steps as f32 * 2.0 * PI;
+      let direction = Vector3D::new(theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin());
+      let solid_angle = theta.sin() * (PI / 2.0 / steps as f32) * (2.0 * PI / steps as f32);
+      expected += sky.radiance(direction) * (theta.cos() * solid_angle / PI);
+    }
+  }
+
+  let actual = floor_irradiance(SkyLight::new(sky.clone(), 1024), false, 0.0);
+  for c in 0..3 {
+    assert!((actual[c] - expected[c]).abs() < 0.03 * expected[c], "expected {:?}, got {:?}", expected, actual);
+  }
+}
+
+#[test]
+fn escaped_rays_see_the_sky_and_the_sun() {
+  let sky = sky(20.0);
+  let mut scene = Scene::new(Camera::default());
+  scene.add_light(sky.sun(1));
+  scene.add_light(SkyLight::new(sky.clone(), 1));
+  let settings = RenderSettings::default();
+  let renderer = Renderer::new(&scene, &settings);
+  let trace = |direction| renderer.trace(&Ray::new(Vector3D::default(), direction), &mut Rng::new(0));
+
+  let up = Vector3D::new(0.0, 1.0, 0.0);
+  assert_eq!(trace(up), sky.radiance(up));
+  let towards_sun = sky.sun_direction();
+  assert_eq!(trace(towards_sun), sky.radiance(towards_sun) + sky.sun(1).environment(towards_sun));
+}
+
+#[test]
+fn scene_files_describe_skies() {
+  let scene = parse("sky { turbidity 4 elevation 25 azimuth -10 samples 8 }").unwrap().scene;
+  assert_eq!(scene.lights.len(), 2);
+  assert_eq!(scene.lights.iter().map(|light| light.samples()).collect::<Vec<_>>(), vec![8, 8]);
+  let expected = Sky { turbidity: 4.0, elevation: 25.0, azimuth: -10.0, intensity: 1.0 };
+  let sample = scene.lights[0].sample(Vector3D::default(), (0.5, 0.5)).unwrap();
+  // somewhere within the sun's disc
+  assert!((sample.direction - expected.sun_direction()).magnitude() < 0.5f32.to_radians());
+
+  let sun = parse("light { direction 0 -1 0 intensity 1 angle 2 samples 4 }").unwrap().scene;
+  assert_eq!(sun.lights[0].samples(), 4);
+  assert_ne!(sun.lights[0].environment(Vector3D::new(0.0, 1.0, 0.0)), Color::BLACK);
+
+  let error = |source| parse(source).expect_err("the scene should not parse").to_string();
+  assert!(error("sky { turbidity 3 }").contains("sky is missing `elevation`"));
+  assert!(error("sky { elevation 30 turbidity 1 }").contains("expected a number from 2 to 10"));
+  assert!(error("sky { elevation 95 }").contains("from 0 to 90"));
+  assert!(error("light { direction 0 -1 0 intensity 1 angle -1 }").contains("from 0 to 180"));
+}
@@ -0,0 +1,123 @@
+use trace::{Ray, Sphere, Vector3D};
+
+fn unit_sphere_at(z: f32) -> Sphere {
+  Sphere { position: Vector3D::new(0.0, 0.0, z), radius: 1.0 }
+}
+
+fn ray(origin: (f32, f32, f32), direction: (f32, f32, f32)) -> Ray {
+  Ray::new(
+    Vector3D::new(origin.0, origin.1, origin.2),
+    Vector3D::new(direction.0, direction.1, direction.2).normalize(),
+  )
+}
+
+fn assert_close(actual: f32, expected: f32) {
+  assert!((actual - expected).abs() < 1e-4, "expected {}, got {}", expected, actual);
+}
+
+#[test]
+fn head_on_hit_returns_the_entry_point() {
+  let sphere = unit_sphere_at(-5.0);
+  let ray = ray((0.0, 0.0, 0.0), (0.0, 0.0, -1.0));
+
+  let (t0, t1) = sphere.intersections(&ray).unwrap();
+  assert_close(t0, 4.0);
+  assert_close(t1, 6.0);
+  assert_close(sphere.intersects(&ray, 0.0, f32::INFINITY).unwrap(), 4.0);
+}
+
+#[test]
+fn miss_returns_none() {
+  let sphere = unit_sphere_at(-5.0);
+  let ray = ray((0.0, 1.5, 0.0), (0.0, 0.0, -1.0));
+
+  assert!(sphere.intersections(&ray).is_none());
+  assert!(sphere.intersects(&ray, 0.0, f32::INFINITY).is_none());
+}
+
+#[test]
+fn tangent_ray_touches_once() {
+  let sphere = unit_sphere_at(-5.0);
+  let ray = ray((0.0, 1.0, 0.0), (0.0, 0.0, -1.0));
+
+  let (t0, t1) = sphere.intersections(&ray).unwrap();
+  assert_close(t0, 5.0);
+  assert_close(t1, 5.0);
+}
+
+#[test]
+fn grazing_ray_enters_and_exits_close_together() {
+  let sphere = unit_sphere_at(-5.0);
+  let ray = ray((0.0, 0.999, 0.0), (0.0, 0.0, -1.0));
+
+  let (t0, t1) = sphere.intersections(&ray).unwrap();
+  let half_chord = (1.0f32 - 0.999 * 0.999).sqrt();
+  assert_close(t0, 5.0 - half_chord);
+  assert_close(t1, 5.0 + half_chord);
+  assert!(t0 < t1);
+}
+
+#[test]
+fn ray_from_the_center_hits_the_far_side() {
+  let sphere = unit_sphere_at(-5.0);
+  let ray = ray((0.0, 0.0, -5.0), (0.0, 1.0, 0.0));
+
+  let (t0, t1) = sphere.intersections(&ray).unwrap();
+  assert_close(t0, -1.0);
+  assert_close(t1, 1.0);
+  assert_close(sphere.intersects(&ray, 0.0, f32::INFINITY).unwrap(), 1.0);
+}
+
+#[test]
+fn ray_from_inside_off_center_hits_the_far_side() {
+  let sphere = unit_sphere_at(-5.0);
+  // inside the sphere and already past its center along the ray
+  let ray = ray((0.0, 0.0, -5.5), (0.0, 0.0, -1.0));
+
+  assert_close(sphere.intersects(&ray, 0.0, f32::INFINITY).unwrap(), 0.5);
+}
+
+#[test]
+fn ray_starting_on_the_surface() {
+  let sphere = unit_sphere_at(-5.0);
+
+  let inwards = ray((0.0, 0.0, -4.0), (0.0, 0.0, -1.0));
+  assert_close(sphere.intersects(&inwards, 0.0, f32::INFINITY).unwrap(), 2.0);
+
+  let outwards = ray((0.0, 0.0, -4.0), (0.0, 0.0, 1.0));
+  assert!(sphere.intersects(&outwards, 1e-4, f32::INFINITY).is_none());
+}
+
+#[test]
+fn sphere_behind_the_ray_is_ignored() {
+  let sphere = unit_sphere_at(5.0);
+  let ray = ray((0.0, 0.0, 0.0), (0.0, 0.0, -1.0));
+
+  let (t0, t1) = sphere.intersections(&ray).unwrap();
+  assert_close(t0, -6.0);
+  assert_close(t1, -4.0);
+  assert!(sphere.intersects(&ray, 0.0, f32::INFINITY).is_none());
+}
+
+#[test]
+fn interval_bounds_are_respected() {
+  let sphere = unit_sphere_at(-5.0);
+  let ray = ray((0.0, 0.0, 0.0), (0.0, 0.0, -1.0));
+
+  assert!(sphere.intersects(&ray, 0.0, 3.0).is_none());
+  assert_close(sphere.intersects(&ray, 4.5, f32::INFINITY).unwrap(), 6.0);
+  assert!(sphere.intersects(&ray, 6.5, f32::INFINITY).is_none());
+  assert!(sphere.intersects(&ray, 4.5, 5.5).is_none());
+}
+
+#[test]
+fn small_distant_sphere_is_hit_precisely() {
+  let sphere = Sphere {
+    position: Vector3D::new(0.0, 0.0, -10000.0),
+    radius: 0.01,
+  };
+  let ray = ray((0.0, 0.0, 0.0), (0.0, 0.0, -1.0));
+
+  let (t0, t1) = sphere.intersections(&ray).unwrap();
+  assert!((t0 - 9999.99).abs() < 1e-2 && (t1 - 10000.01).abs() < 1e-2);
+}
@@ -0,0 +1,84 @@
+use trace::{render, Camera, Color, DirectionalLight, Filter, Plane, RenderSettings, Scene, Sphere, SphereLight, Vector3D};
+
+fn scene() -> Scene {
+  let mut scene = Scene::new(Camera::default());
+  scene.add(Sphere { position: Vector3D::new(0.0, 0.0, -5.0), radius: 1.0 }, Color::new(1.0, 0.2, 0.2));
+  scene.add(Sphere { position: Vector3D::new(0.6, 0.3, -3.0), radius: 0.3 }, Color::new(0.2, 0.2, 1.0));
+  scene.add(
+    Plane { point: Vector3D::new(0.0, -1.0, 0.0), normal: Vector3D::new(0.0, 1.0, 0.0) },
+    Color::new(0.8, 0.8, 0.8),
+  );
+  scene.add_light(DirectionalLight {
+    direction: Vector3D::new(-1.0, -1.0, -1.0),
+    color: Color::WHITE,
+    intensity: 0.9,
+    angle: 0.0,
+    samples: 1,
+  });
+  // random shadow rays must not depend on the thread either
+  scene.add_light(SphereLight { position: Vector3D::new(-1.0, 1.0, -3.0), radius: 0.3, color: Color::WHITE, intensity: 2.0, samples: 3 });
+  scene
+}
+
+#[test]
+fn output_does_not_depend_on_thread_count_or_tile_size() {
+  let scene = scene();
+  let base = RenderSettings { width: 97, height: 61, samples: 4, threads: 1, tile_size: 97, ..RenderSettings::default() };
+  let reference = render(&scene, &base);
+
+  for &(threads, tile_size) in &[(1, 8), (2, 16), (4, 7), (8, 32), (0, 1)] {
+    let settings = RenderSettings { threads, tile_size, ..base.clone() };
+    let image = render(&scene, &settings);
+    assert!(image.pixels == reference.pixels, "{} threads with {}px tiles differ", threads, tile_size);
+  }
+}
+
+#[test]
+fn filtered_output_does_not_depend_on_thread_count() {
+  let scene = scene();
+  for &filter in &[Filter::Tent, Filter::Lanczos] {
+    let base =
+      RenderSettings { width: 53, height: 37, samples: 5, filter, threads: 1, tile_size: 8, ..RenderSettings::default() };
+    let reference = render(&scene, &base);
+
+    for &threads in &[2, 3, 8] {
+      let image = render(&scene, &RenderSettings { threads, ..base.clone() });
+      assert!(image.pixels == reference.pixels, "{:?} with {} threads differs", filter, threads);
+    }
+  }
+}
+
+#[test]
+fn aov_layers_do_not_depend_on_tiles() {
+  let scene = scene();
+  let settings =
+    RenderSettings { width: 41, height: 23, samples: 1, threads: 1, tile_size: 41, aovs: true, ..RenderSettings::default() };
+  let reference = render(&scene, &settings);
+  assert_eq!(reference.layers.len(), 4);
+
+  let image = render(&scene, &RenderSettings { threads: 3, tile_size: 6, ..settings });
+  for (layer, expected) in image.layers.iter().zip(&reference.layers) {
+    assert_eq!(layer.name, expected.name);
+    assert!(layer.samples == expected.samples, "{} layer differs", layer.name);
+  }
+
+  // the sphere in the middle of the frame is the first object
+  let center = 20 + 11 * 41;
+  assert_eq!(image.layer("id").unwrap().pixel(center), &[0.0]);
+  assert!((image.layer("depth").unwrap().pixel(center)[0] - 4.0).abs() < 1e-3);
+  assert!(render(&scene, &RenderSettings { aovs: false, ..settings }).layers.is_empty());
+}
+
+#[test]
+fn ray_counts_add_up_across_threads() {
+  let scene = scene();
+  let settings = RenderSettings { width: 31, height: 17, samples: 2, threads: 1, tile_size: 8, ..RenderSettings::default() };
+  scene.bvh().reset_stats();
+  render(&scene, &settings);
+  let reference = scene.stats();
+  assert!(reference.rays > 31 * 17 * 2);
+
+  scene.bvh().reset_stats();
+  render(&scene, &RenderSettings { threads: 4, ..settings });
+  assert_eq!(scene.stats(), reference);
+}
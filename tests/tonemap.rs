@@ -0,0 +1,112 @@
+use trace::tonemap::srgb_encode;
+use trace::{Color, Dither, DisplayTransform, Image, ToneMapper};
+
+const MAPPERS: [ToneMapper; 5] = [
+  ToneMapper::Clamp,
+  ToneMapper::Reinhard,
+  ToneMapper::ExtendedReinhard { white: 4.0 },
+  ToneMapper::Aces,
+  ToneMapper::Hable,
+];
+
+#[test]
+fn srgb_curve_matches_reference_values() {
+  assert_eq!(srgb_encode(0.0), 0.0);
+  assert!((srgb_encode(1.0) - 1.0).abs() < 1e-6);
+  assert!((srgb_encode(0.5) - 0.735357).abs() < 1e-5);
+  assert!((srgb_encode(0.001) - 0.01292).abs() < 1e-6);
+  // the linear and power segments meet without a jump
+  assert!((srgb_encode(0.0031308) - srgb_encode(0.0031309)).abs() < 1e-5);
+}
+
+#[test]
+fn tone_mappers_are_monotonic_and_bounded() {
+  for &mapper in &MAPPERS {
+    let mut previous = -1.0;
+    for i in 0..2000 {
+      let value = mapper.apply(Color::gray(i as f32 * 0.01)).r;
+      assert!((0.0..=1.0).contains(&value), "{:?} maps to {}", mapper, value);
+      assert!(value >= previous, "{:?} decreases at {}", mapper, i);
+      previous = value;
+    }
+    assert!(mapper.apply(Color::gray(0.0)).r < 1e-3, "{:?} lifts black", mapper);
+    assert_eq!(mapper.apply(Color::gray(-5.0)), mapper.apply(Color::gray(0.0)));
+  }
+}
+
+#[test]
+fn tone_mappers_hit_their_reference_points() {
+  assert_eq!(ToneMapper::Clamp.apply(Color::new(0.25, 3.0, 0.5)), Color::new(0.25, 1.0, 0.5));
+  assert_eq!(ToneMapper::Reinhard.apply(Color::gray(1.0)), Color::gray(0.5));
+  assert!((ToneMapper::ExtendedReinhard { white: 4.0 }.apply(Color::gray(4.0)).r - 1.0).abs() < 1e-6);
+  assert_eq!(ToneMapper::ExtendedReinhard { white: 4.0 }.apply(Color::gray(9.0)), Color::gray(1.0));
+  assert!((ToneMapper::Hable.apply(Color::gray(5.6)).r - 1.0).abs() < 1e-5);
+  assert!(ToneMapper::Aces.apply(Color::gray(100.0)).r > 0.99);
+  // gray stays gray through the fitted ACES matrices
+  let aces = ToneMapper::Aces.apply(Color::gray(0.18));
+  assert!((aces.r - aces.g).abs() < 1e-3 && (aces.g - aces.b).abs() < 1e-3, "{:?}", aces);
+}
+
+#[test]
+fn exposure_is_in_stops() {
+  let display = DisplayTransform { exposure: 2.0, ..DisplayTransform::default() };
+  assert_eq!(display.apply(Color::gray(0.125)), DisplayTransform::default().apply(Color::gray(0.5)));
+}
+
+#[test]
+fn quantization_rounds_encoded_values() {
+  let mut image = Image::new(3, 1);
+  image.pixels = vec![Color::gray(0.0), Color::gray(0.5), Color::new(1.0, 7.0, -1.0)];
+  assert_eq!(DisplayTransform::default().to_rgb8(&image), vec![0, 0, 0, 188, 188, 188, 255, 255, 0]);
+}
+
+#[test]
+fn dither_thresholds_cover_every_level_once() {
+  for &(dither, size) in &[(Dither::Ordered, 8), (Dither::BlueNoise, 64)] {
+    let mut thresholds: Vec<f32> = (0..size * size).map(|i| dither.threshold(i % size, i / size)).collect();
+    assert_eq!(dither.threshold(3, 5), dither.threshold(3 + size, 5 + 2 * size));
+
+    thresholds.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    for (rank, &threshold) in thresholds.iter().enumerate() {
+      assert_eq!(threshold, (rank as f32 + 0.5) / (size * size) as f32, "{:?}", dither);
+    }
+  }
+
+  assert_eq!(Dither::Ordered.threshold(0, 0), 0.5 / 64.0);
+  assert_eq!(Dither::Ordered.threshold(1, 1), 16.5 / 64.0);
+}
+
+#[test]
+fn blue_noise_spreads_low_thresholds_apart() {
+  // the darkest 1/16th of the mask should have no two points touching
+  let size = 64;
+  let low: Vec<(isize, isize)> = (0..size * size)
+    .filter(|&i| Dither::BlueNoise.threshold(i % size, i / size) < 1.0 / 16.0)
+    .map(|i| ((i % size) as isize, (i / size) as isize))
+    .collect();
+  assert_eq!(low.len(), size * size / 16);
+
+  for (i, a) in low.iter().enumerate() {
+    for b in &low[i + 1..] {
+      let dx = (a.0 - b.0).rem_euclid(64).min((b.0 - a.0).rem_euclid(64));
+      let dy = (a.1 - b.1).rem_euclid(64).min((b.1 - a.1).rem_euclid(64));
+      assert!(dx.max(dy) > 1, "{:?} and {:?} touch", a, b);
+    }
+  }
+}
+
+#[test]
+fn dithering_preserves_the_average_level() {
+  // a flat value between two 8-bit levels
+  let linear = 0.1278f32;
+  let level = srgb_encode(linear) * 255.0;
+  let mut image = Image::new(64, 64);
+  image.pixels = vec![Color::gray(linear); 64 * 64];
+
+  for &dither in &[Dither::Ordered, Dither::BlueNoise] {
+    let rgb = DisplayTransform { dither, ..DisplayTransform::default() }.to_rgb8(&image);
+    let mean = rgb.iter().map(|&v| v as f32).sum::<f32>() / rgb.len() as f32;
+    assert!((mean - level).abs() < 0.02, "{:?}: {} vs {}", dither, mean, level);
+    assert!(rgb.iter().all(|&v| v as f32 == level.floor() || v as f32 == level.ceil()));
+  }
+}
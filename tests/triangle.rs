@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use trace::{Ray, Triangle, TriangleMesh, Vector3D};
+
+/// A unit square in the z = 0 plane split along its diagonal.
+fn quad() -> Arc<TriangleMesh> {
+  Arc::new(TriangleMesh {
+    positions: vec![
+      Vector3D::new(0.0, 0.0, 0.0),
+      Vector3D::new(1.0, 0.0, 0.0),
+      Vector3D::new(1.0, 1.0, 0.0),
+      Vector3D::new(0.0, 1.0, 0.0),
+    ],
+    normals: vec![
+      Vector3D::new(0.0, 0.0, 1.0),
+      Vector3D::new(1.0, 0.0, 1.0).normalize(),
+      Vector3D::new(0.0, 0.0, 1.0),
+      Vector3D::new(0.0, 0.0, 1.0),
+    ],
+    uvs: vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)],
+    indices: vec![[0, 1, 2], [0, 2, 3]],
+  })
+}
+
+fn down_at(x: f32, y: f32) -> Ray {
+  Ray::new(Vector3D::new(x, y, 1.0), Vector3D::new(0.0, 0.0, -1.0))
+}
+
+#[test]
+fn hits_inside_and_misses_outside() {
+  let triangles = TriangleMesh::triangles(&quad());
+
+  assert_eq!(triangles[0].intersects(&down_at(0.75, 0.25), 0.0, f32::INFINITY), Some(1.0));
+  assert_eq!(triangles[0].intersects(&down_at(0.25, 0.75), 0.0, f32::INFINITY), None);
+  assert_eq!(triangles[1].intersects(&down_at(0.25, 0.75), 0.0, f32::INFINITY), Some(1.0));
+  assert_eq!(triangles[0].intersects(&down_at(0.75, 0.25), 0.0, 0.5), None);
+}
+
+#[test]
+fn shared_edges_have_no_cracks() {
+  let triangles = TriangleMesh::triangles(&quad());
+
+  // sweep rays along and slightly off the shared diagonal, from an oblique
+  // direction so the edge test is not trivially axis aligned
+  for i in 1..1000 {
+    let s = i as f32 / 1000.0;
+    for &offset in &[0.0, 1e-7, -1e-7] {
+      let target = Vector3D::new(s + offset, s, 0.0);
+      let origin = Vector3D::new(0.3, -0.2, 2.0);
+      let ray = Ray::new(origin, (target - origin).normalize());
+
+      let hits = triangles.iter().filter(|t| t.intersects(&ray, 0.0, f32::INFINITY).is_some()).count();
+      assert!(hits >= 1, "ray through ({}, {}) slipped between triangles", s + offset, s);
+    }
+  }
+}
+
+#[test]
+fn interpolates_vertex_attributes() {
+  let triangle = Triangle::new(quad(), 0);
+  let point = Vector3D::new(0.75, 0.25, 0.0);
+
+  let (w0, w1, w2) = triangle.barycentric(&point);
+  assert!((w0 - 0.25).abs() < 1e-6 && (w1 - 0.5).abs() < 1e-6 && (w2 - 0.25).abs() < 1e-6);
+
+  let (u, v) = triangle.uv(&point).unwrap();
+  assert!((u - 0.75).abs() < 1e-6 && (v - 0.25).abs() < 1e-6);
+
+  let normal = triangle.surface_normal(&point);
+  assert!(normal.x > 0.0 && (normal.magnitude() - 1.0).abs() < 1e-6);
+}
+
+#[test]
+fn falls_back_to_the_face_normal() {
+  let mut mesh = (*quad()).clone();
+  mesh.normals.clear();
+  mesh.uvs.clear();
+  let triangle = Triangle::new(Arc::new(mesh), 0);
+
+  assert_eq!(triangle.surface_normal(&Vector3D::new(0.75, 0.25, 0.0)), Vector3D::new(0.0, 0.0, 1.0));
+  assert_eq!(triangle.uv(&Vector3D::new(0.75, 0.25, 0.0)), None);
+}